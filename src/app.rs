@@ -1,8 +1,38 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::AbortHandle;
 use tui_textarea::TextArea;
+use ratatui::text::Line;
 use ratatui::widgets::ListState;
 
-use crate::models::{AuthType, HttpMethod, KeyValue, Request, RequestState, Response};
-use crate::utils::{scroll_by, single_line_textarea, textarea_value};
+use crate::binary;
+use crate::codegen::{self, Language};
+use crate::curl;
+use crate::files;
+use crate::graphql::{self, Schema};
+use crate::highlight::{self, Syntax};
+use crate::http::{DigestSessions, HttpResult, OAuthToken, RequestData, WsCommand};
+use crate::jq;
+use crate::json_tree::JsonTree;
+use crate::models::{
+    AuthType, BodyType, Collection, CookieJar, Environment, EnvironmentId, Folder, HttpMethod, KeyValue, Node,
+    OAuthConfig, Request, RequestId, RequestState, Row, Setting, Settings, SettingsOverride, StreamEvent,
+};
+use crate::search::Search;
+use crate::storage::{Storage, Workspace, WorkspaceRef};
+use crate::template;
+use crate::utils::{
+    common_prefix, copy_to_clipboard, format_json_if_valid, scroll_by, single_line_textarea,
+    textarea_value,
+};
+
+/// Lines kept above a search hit when scrolling to it.
+const SEARCH_CONTEXT_LINES: usize = 3;
+
+/// Bodies larger than this are shown without syntax highlighting.
+const HIGHLIGHT_MAX_BYTES: usize = 1024 * 1024;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Panel {
@@ -39,6 +69,15 @@ pub enum RequestTab {
     Auth,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseTab {
+    #[default]
+    Body,
+    Headers,
+    Cookies,
+    Timing,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum EditFocus {
     #[default]
@@ -47,6 +86,120 @@ pub enum EditFocus {
     KeyValue,
     Body,
     Auth,
+    Prompt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    NewFolder,
+    Rename,
+    NewEnvironment,
+    RenameEnvironment,
+    ImportCurl,
+    HeaderFilter,
+    ResponseFilter,
+    Search,
+    SearchBackward,
+    EditSetting,
+    EditCookie,
+    BodyFile,
+    SaveBody,
+}
+
+impl PromptKind {
+    pub fn is_search(self) -> bool {
+        matches!(self, PromptKind::Search | PromptKind::SearchBackward)
+    }
+
+    /// Whether the prompt takes a file path, completed with Tab.
+    pub fn is_path(self) -> bool {
+        matches!(self, PromptKind::BodyFile | PromptKind::SaveBody)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            PromptKind::NewFolder => "New Folder",
+            PromptKind::Rename => "Rename",
+            PromptKind::NewEnvironment => "New Environment",
+            PromptKind::RenameEnvironment => "Rename Environment",
+            PromptKind::ImportCurl => "Paste curl Command",
+            PromptKind::HeaderFilter => "Filter Headers",
+            PromptKind::ResponseFilter => "jq Filter",
+            PromptKind::Search => "Search",
+            PromptKind::SearchBackward => "Search Backward",
+            PromptKind::EditSetting => "Edit Setting",
+            PromptKind::EditCookie => "Cookie Value",
+            PromptKind::BodyFile => "Body File (Tab completes)",
+            PromptKind::SaveBody => "Save Body To (Tab completes)",
+        }
+    }
+}
+
+/// One-line status shown in place of the key hints until the next key press.
+pub struct Message {
+    pub text: String,
+    pub is_error: bool,
+}
+
+static IDLE: RequestState = RequestState::Idle;
+
+/// Identifies one send of one request, so its result can be routed back to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ticket {
+    request: RequestId,
+    seq: u64,
+    /// The environment active at send time, whose jar keeps the cookies set;
+    /// `None` for the jar used with no environment.
+    environment: Option<EnvironmentId>,
+}
+
+/// A send still waiting for its result.
+struct InFlight {
+    seq: u64,
+    abort: AbortHandle,
+    started: Instant,
+    /// Body bytes received so far, and the expected total if announced.
+    received: u64,
+    total: Option<u64>,
+    /// Commands for an open WebSocket; taken once it is being closed.
+    socket: Option<UnboundedSender<WsCommand>>,
+    /// What the send is waiting for before the request goes out.
+    status: Option<String>,
+}
+
+#[derive(Default)]
+struct Exchange {
+    state: RequestState,
+    in_flight: Option<InFlight>,
+}
+
+/// Which settings the settings overlay edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SettingsScope {
+    #[default]
+    Global,
+    Request,
+}
+
+impl SettingsScope {
+    pub fn title(self) -> &'static str {
+        match self {
+            SettingsScope::Global => "Global",
+            SettingsScope::Request => "This request",
+        }
+    }
+}
+
+/// The code snippet popup for the current request.
+pub struct ExportView {
+    pub language: Language,
+    pub code: String,
+    pub scroll: usize,
+}
+
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: TextArea<'static>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -138,72 +291,35 @@ impl KvEditor {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub enum AuthField {
-    #[default]
-    First,
-    Second,
-}
-
-impl AuthField {
-    pub fn toggle(self) -> Self {
-        match self {
-            AuthField::First => AuthField::Second,
-            AuthField::Second => AuthField::First,
-        }
-    }
-}
-
+/// Inputs for the current auth type's fields, in `AuthType::fields` order.
+#[derive(Default)]
 pub struct AuthEditor {
-    pub field: AuthField,
-    pub first_input: TextArea<'static>,
-    pub second_input: TextArea<'static>,
-}
-
-impl Default for AuthEditor {
-    fn default() -> Self {
-        Self {
-            field: AuthField::First,
-            first_input: single_line_textarea(""),
-            second_input: single_line_textarea(""),
-        }
-    }
+    pub field: usize,
+    pub inputs: Vec<TextArea<'static>>,
 }
 
 impl AuthEditor {
     pub fn reset(&mut self) {
-        self.field = AuthField::First;
-        self.first_input = single_line_textarea("");
-        self.second_input = single_line_textarea("");
+        self.field = 0;
+        self.inputs.clear();
     }
 
-    pub fn toggle_field(&mut self) {
-        self.field = self.field.toggle();
+    pub fn next_field(&mut self) {
+        self.field = (self.field + 1) % self.inputs.len().max(1);
     }
 
-    pub fn current_input_mut(&mut self) -> &mut TextArea<'static> {
-        match self.field {
-            AuthField::First => &mut self.first_input,
-            AuthField::Second => &mut self.second_input,
-        }
+    pub fn prev_field(&mut self) {
+        let len = self.inputs.len().max(1);
+        self.field = (self.field + len - 1) % len;
+    }
+
+    pub fn current_input_mut(&mut self) -> Option<&mut TextArea<'static>> {
+        self.inputs.get_mut(self.field)
     }
 
     pub fn sync_from_auth(&mut self, auth: &AuthType) {
-        match auth {
-            AuthType::None => self.reset(),
-            AuthType::Basic { username, password } => {
-                self.first_input = single_line_textarea(username);
-                self.second_input = single_line_textarea(password);
-            }
-            AuthType::Bearer { token } => {
-                self.first_input = single_line_textarea(token);
-                self.second_input = single_line_textarea("");
-            }
-            AuthType::ApiKey { key, value } => {
-                self.first_input = single_line_textarea(key);
-                self.second_input = single_line_textarea(value);
-            }
-        }
+        self.inputs = auth.fields().into_iter().map(|(_, value)| single_line_textarea(value)).collect();
+        self.field = self.field.min(self.inputs.len().saturating_sub(1));
     }
 }
 
@@ -213,11 +329,37 @@ pub struct App<'a> {
     pub should_quit: bool,
     pub show_help: bool,
     pub help_scroll: usize,
+    pub message: Option<Message>,
+    pub prompt: Option<Prompt>,
+    pub export: Option<ExportView>,
+
+    // Persistence
+    storage: Option<Storage>,
 
     // Sidebar
-    pub requests: Vec<Request>,
+    pub collection: Collection,
     pub sidebar_state: ListState,
-    pub editing_request_idx: Option<usize>,
+    pub editing_request: Option<RequestId>,
+    pub moving_request: Option<RequestId>,
+
+    // Environments
+    pub environments: Vec<Environment>,
+    pub active_environment: Option<usize>,
+    pub show_environments: bool,
+    pub environment_cursor: usize,
+    pub env_editor: KvEditor,
+
+    // Client settings
+    pub settings: Settings,
+    pub show_settings: bool,
+    pub settings_scope: SettingsScope,
+    pub settings_cursor: usize,
+
+    // Cookies
+    /// Jar used while no environment is active.
+    pub cookies: CookieJar,
+    pub show_cookies: bool,
+    pub cookie_cursor: usize,
 
     // Request editor
     pub active_tab: RequestTab,
@@ -234,16 +376,58 @@ pub struct App<'a> {
     pub headers_editor: KvEditor,
 
     // Body
+    pub body_type: BodyType,
+    pub form: Vec<KeyValue>,
+    pub form_editor: KvEditor,
+    pub body_file: String,
+    /// Size of `body_file`, or `None` if it can't be read.
+    pub body_file_size: Option<u64>,
     pub body_editor: TextArea<'a>,
+    pub body_preview: Vec<Line<'static>>,
     pub json_error: Option<String>,
+    /// Variables of a GraphQL body, whose query is in `body_editor`.
+    pub variables_editor: TextArea<'a>,
+    pub variables_preview: Vec<Line<'static>>,
+    /// Editing the GraphQL variables rather than the query.
+    pub editing_variables: bool,
+    /// Introspected GraphQL schemas, by endpoint URL.
+    schemas: HashMap<String, Schema>,
 
     // Auth
     pub auth: AuthType,
     pub auth_editor: AuthEditor,
+    /// OAuth2 access tokens, by the expanded config they were obtained with.
+    oauth_tokens: HashMap<OAuthConfig, OAuthToken>,
+    /// The Digest challenges answered so far, shared with sends in flight.
+    digest_sessions: DigestSessions,
+
+    /// Client setting overrides for the request in the editor.
+    pub request_settings: SettingsOverride,
 
     // Response
-    pub request_state: RequestState,
+    /// Last outcome and any send in flight, per saved request.
+    exchanges: HashMap<RequestId, Exchange>,
+    request_seq: u64,
+    /// Highlighted, formatted response body; built once per response.
+    pub response_lines: Vec<Line<'static>>,
+    pub search: Option<Search>,
+    /// Scroll position when the search prompt opened, restored on cancel.
+    search_origin: usize,
+    /// jq filter over the response body; saved with the request being edited.
+    pub response_filter: String,
+    pub filter_error: Option<String>,
+    /// Foldable view of a JSON response body, if it parsed.
+    pub json_tree: Option<JsonTree>,
+    pub tree_view: bool,
+    /// Show the body as a hex dump; binary bodies always are.
+    pub hex_view: bool,
+    /// `z` was pressed in the tree view; the next key is a fold command.
+    pub fold_pending: bool,
+    pub response_tab: ResponseTab,
     pub response_scroll: usize,
+    /// Scroll to each new event as it arrives.
+    follow_events: bool,
+    pub header_filter: String,
 }
 
 impl<'a> App<'a> {
@@ -251,14 +435,50 @@ impl<'a> App<'a> {
         let mut body_editor = TextArea::default();
         body_editor.set_cursor_line_style(ratatui::style::Style::default());
 
+        // A file we failed to read is left alone: saving over it would lose history.
+        let mut storage = Storage::open_default();
+        let (workspace, message) = match storage.as_ref().map(Storage::load) {
+            Some(Ok(workspace)) => (workspace, None),
+            Some(Err(e)) => {
+                storage = None;
+                let text = format!("History not loaded: {:#}", e);
+                (Workspace::default(), Some(Message { text, is_error: true }))
+            }
+            None => (Workspace::default(), None),
+        };
+        let Workspace { collection, environments, active_environment, settings, cookies } = workspace;
+        let active_environment = active_environment.filter(|&i| i < environments.len());
+
+        let mut sidebar_state = ListState::default();
+        if !collection.is_empty() {
+            sidebar_state.select(Some(0));
+        }
+
         Self {
             focused_panel: Panel::default(),
             should_quit: false,
             show_help: false,
             help_scroll: 0,
-            requests: vec![],
-            sidebar_state: ListState::default(),
-            editing_request_idx: None,
+            message,
+            prompt: None,
+            export: None,
+            storage,
+            collection,
+            sidebar_state,
+            editing_request: None,
+            moving_request: None,
+            environments,
+            active_environment,
+            show_environments: false,
+            environment_cursor: 0,
+            env_editor: KvEditor::default(),
+            settings,
+            show_settings: false,
+            settings_scope: SettingsScope::default(),
+            settings_cursor: 0,
+            cookies,
+            show_cookies: false,
+            cookie_cursor: 0,
             active_tab: RequestTab::default(),
             edit_focus: EditFocus::None,
             url_input: single_line_textarea(""),
@@ -267,12 +487,38 @@ impl<'a> App<'a> {
             params_editor: KvEditor::default(),
             headers: vec![],
             headers_editor: KvEditor::default(),
+            body_type: BodyType::default(),
+            form: vec![],
+            form_editor: KvEditor::default(),
+            body_file: String::new(),
+            body_file_size: None,
             body_editor,
+            body_preview: vec![],
             json_error: None,
+            variables_editor: TextArea::default(),
+            variables_preview: vec![],
+            editing_variables: false,
+            schemas: HashMap::new(),
             auth: AuthType::None,
             auth_editor: AuthEditor::default(),
-            request_state: RequestState::default(),
+            oauth_tokens: HashMap::new(),
+            digest_sessions: DigestSessions::default(),
+            request_settings: SettingsOverride::default(),
+            exchanges: HashMap::new(),
+            request_seq: 0,
+            response_lines: vec![],
+            search: None,
+            search_origin: 0,
+            response_filter: String::new(),
+            filter_error: None,
+            json_tree: None,
+            tree_view: false,
+            hex_view: false,
+            fold_pending: false,
+            response_tab: ResponseTab::default(),
             response_scroll: 0,
+            follow_events: true,
+            header_filter: String::new(),
         }
     }
 
@@ -280,6 +526,14 @@ impl<'a> App<'a> {
         textarea_value(&self.url_input)
     }
 
+    pub fn show_info(&mut self, text: impl Into<String>) {
+        self.message = Some(Message { text: text.into(), is_error: false });
+    }
+
+    pub fn show_error(&mut self, text: impl Into<String>) {
+        self.message = Some(Message { text: text.into(), is_error: true });
+    }
+
     pub fn quit(&mut self) {
         self.should_quit = true;
     }
@@ -291,6 +545,84 @@ impl<'a> App<'a> {
         }
     }
 
+    /// The editor contents as they would be sent, with `{{variables}}`
+    /// expanded from the active environment.
+    pub fn request_data(&self) -> Result<RequestData, String> {
+        let mut vars = template::Expander::new(self.active_variables());
+        let settings = self.effective_settings();
+        let data = RequestData {
+            method: self.method,
+            url: vars.text(self.url().trim()),
+            params: vars.key_values(&self.params),
+            headers: vars.key_values(&self.headers),
+            body: vars.text(&self.body()),
+            body_type: self.body_type,
+            form: vars.key_values(&self.form),
+            body_file: vars.text(&self.body_file),
+            variables: vars.text(&self.variables()),
+            auth: vars.auth(&self.auth),
+            token: None,
+            digest: self.digest_sessions.clone(),
+            settings: settings.client,
+            cookies: settings.send_cookies.then(|| self.cookie_jar().clone()),
+        };
+        vars.finish().map(|_| self.with_token(data))
+    }
+
+    /// Send an OAuth2 auth as its cached token while that is fresh; otherwise
+    /// hand the send what is cached, for it to renew.
+    fn with_token(&self, mut data: RequestData) -> RequestData {
+        let AuthType::OAuth2(config) = &data.auth else { return data };
+        match self.oauth_tokens.get(config) {
+            Some(token) if token.is_fresh() => {
+                data.auth = AuthType::Bearer { token: token.access_token.clone() };
+            }
+            cached => data.token = cached.cloned(),
+        }
+        data
+    }
+
+    // Export
+    pub fn open_export(&mut self) {
+        let language = self.export.as_ref().map(|e| e.language).unwrap_or_default();
+        match self.request_data() {
+            Ok(data) => {
+                let code = codegen::generate(language, &data);
+                self.export = Some(ExportView { language, code, scroll: 0 });
+            }
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    pub fn close_export(&mut self) {
+        self.export = None;
+    }
+
+    pub fn export_switch_language(&mut self, forward: bool) {
+        let Some(export) = &self.export else { return };
+        let language = if forward { export.language.next() } else { export.language.prev() };
+        if let Ok(data) = self.request_data() {
+            let code = codegen::generate(language, &data);
+            self.export = Some(ExportView { language, code, scroll: 0 });
+        }
+    }
+
+    pub fn export_scroll(&mut self, delta: isize) {
+        if let Some(export) = &mut self.export {
+            let lines = export.code.lines().count();
+            scroll_by(&mut export.scroll, delta, lines);
+        }
+    }
+
+    pub fn copy_export(&mut self) {
+        let Some(export) = &self.export else { return };
+        let language = export.language.name();
+        match copy_to_clipboard(&export.code) {
+            Ok(()) => self.show_info(format!("Copied {} snippet to clipboard", language)),
+            Err(e) => self.show_error(format!("Copy failed: {}", e)),
+        }
+    }
+
     pub fn is_editing(&self) -> bool {
         self.edit_focus != EditFocus::None
     }
@@ -303,21 +635,70 @@ impl<'a> App<'a> {
         self.body_editor = TextArea::new(text.lines().map(String::from).collect());
         self.body_editor.set_cursor_line_style(ratatui::style::Style::default());
         self.validate_json();
+        self.refresh_body_preview();
+    }
+
+    pub fn variables(&self) -> String {
+        self.variables_editor.lines().join("\n")
     }
 
+    pub fn set_variables(&mut self, text: &str) {
+        self.variables_editor = TextArea::new(text.lines().map(String::from).collect());
+        self.variables_editor.set_cursor_line_style(ratatui::style::Style::default());
+        self.validate_json();
+        self.refresh_body_preview();
+    }
+
+    pub fn set_body_file(&mut self, path: String) {
+        self.body_file_size = std::fs::metadata(files::expand_home(&path))
+            .ok()
+            .filter(|meta| meta.is_file())
+            .map(|meta| meta.len());
+        self.body_file = path;
+    }
+
+    /// Re-highlight the read-only body view after the body changes.
+    fn refresh_body_preview(&mut self) {
+        let content_type = self.body_type.content_type().or_else(|| {
+            self.headers
+                .iter()
+                .find(|h| h.enabled && h.key.eq_ignore_ascii_case("content-type"))
+                .map(|h| h.value.as_str())
+        });
+        if self.body_type == BodyType::GraphQl {
+            self.body_preview = highlight::highlight(&self.body(), Syntax::Plain);
+            let variables = format_json_if_valid(&self.variables());
+            self.variables_preview = highlight::highlight(&variables, Syntax::Json);
+            return;
+        }
+        let body = format_json_if_valid(&self.body());
+        self.body_preview = highlight::highlight(&body, Syntax::detect(content_type, &body));
+    }
+
+    /// Pretty-print the body, or the GraphQL variables when editing those.
     pub fn format_json(&mut self) {
-        let text = self.body();
+        let text = if self.editing_variables { self.variables() } else { self.body() };
         if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text)
             && let Ok(formatted) = serde_json::to_string_pretty(&value)
         {
-            self.set_body(&formatted);
+            if self.editing_variables {
+                self.set_variables(&formatted);
+            } else {
+                self.set_body(&formatted);
+            }
             self.json_error = None;
         }
     }
 
+    /// Check the body parses, unless it was given a non-JSON type. For
+    /// GraphQL, check the variables instead.
     pub fn validate_json(&mut self) {
+        if self.body_type == BodyType::GraphQl {
+            self.json_error = graphql::envelope("", &self.variables()).err();
+            return;
+        }
         let text = self.body();
-        if text.trim().is_empty() {
+        if text.trim().is_empty() || !matches!(self.body_type, BodyType::Auto | BodyType::Json) {
             self.json_error = None;
         } else {
             match serde_json::from_str::<serde_json::Value>(&text) {
@@ -337,98 +718,600 @@ impl<'a> App<'a> {
     }
 
     // Sidebar
-    pub fn selected_request(&self) -> usize {
-        self.sidebar_state.selected().unwrap_or(0)
+    pub fn sidebar_rows(&self) -> Vec<Row> {
+        self.collection.visible_rows()
+    }
+
+    pub fn selected_row(&self) -> Option<Row> {
+        let rows = self.sidebar_rows();
+        rows.into_iter().nth(self.sidebar_state.selected().unwrap_or(0))
+    }
+
+    fn select_path(&mut self, path: &[usize]) {
+        let rows = self.sidebar_rows();
+        if let Some(i) = rows.iter().position(|row| row.path == path) {
+            self.sidebar_state.select(Some(i));
+        }
     }
 
     pub fn select_next_request(&mut self) {
-        if self.requests.is_empty() {
+        let len = self.sidebar_rows().len();
+        if len == 0 {
             return;
         }
         let i = match self.sidebar_state.selected() {
-            Some(i) => (i + 1) % self.requests.len(),
+            Some(i) => (i + 1) % len,
             None => 0,
         };
         self.sidebar_state.select(Some(i));
     }
 
     pub fn select_prev_request(&mut self) {
-        if self.requests.is_empty() {
+        let len = self.sidebar_rows().len();
+        if len == 0 {
             return;
         }
         let i = match self.sidebar_state.selected() {
-            Some(i) => if i == 0 { self.requests.len() - 1 } else { i - 1 },
+            Some(i) => if i == 0 { len - 1 } else { i - 1 },
             None => 0,
         };
         self.sidebar_state.select(Some(i));
     }
 
+    /// Folder new items go into: the selected folder, or the parent of the
+    /// selected request. Opens the folder so the new item is visible.
+    fn target_folder(&mut self) -> Vec<usize> {
+        let Some(row) = self.selected_row() else { return vec![] };
+        match self.collection.node_mut(&row.path) {
+            Some(Node::Folder(folder)) => {
+                folder.expanded = true;
+                row.path
+            }
+            _ => row.path[..row.depth].to_vec(),
+        }
+    }
+
+    fn insert_node(&mut self, node: Node) -> Option<Vec<usize>> {
+        let parent = self.target_folder();
+        let path = self.collection.insert(&parent, node)?;
+        self.select_path(&path);
+        Some(path)
+    }
+
     pub fn add_request(&mut self, request: Request) {
-        self.requests.insert(0, request);
-        self.sidebar_state.select(Some(0));
-        self.editing_request_idx = Some(0);
+        if let Some(path) = self.insert_node(Node::Request(Box::new(request)))
+            && let Some(Node::Request(req)) = self.collection.node(&path)
+        {
+            self.editing_request = Some(req.id);
+        }
+        self.persist();
+        self.refresh_response_body();
     }
 
     pub fn new_request(&mut self) {
-        self.requests.insert(0, Request::default());
-        self.sidebar_state.select(Some(0));
+        self.add_request(Request::default());
 
         // Clear editor state for new request
-        self.editing_request_idx = Some(0);
         self.url_input = single_line_textarea("");
         self.method = HttpMethod::Get;
         self.params = vec![];
         self.headers = vec![];
+        self.body_type = BodyType::default();
+        self.form = vec![];
+        self.set_body_file(String::new());
+        self.set_variables("");
         self.set_body("");
         self.auth = AuthType::None;
         self.params_editor.reset();
         self.headers_editor.reset();
+        self.form_editor.reset();
         self.auth_editor.reset();
-        self.request_state = RequestState::default();
-    }
-
-    pub fn update_request(&mut self, idx: usize, request: Request) {
-        if let Some(existing) = self.requests.get_mut(idx) {
+        self.response_filter.clear();
+        self.request_settings = SettingsOverride::default();
+        self.refresh_response_body();
+    }
+
+    /// Replace a saved request with the editor contents, keeping its identity,
+    /// name and response filter.
+    pub fn update_request(&mut self, id: RequestId, mut request: Request) {
+        if let Some(existing) = self.collection.find_request_mut(id) {
+            request.id = existing.id;
+            request.name = std::mem::take(&mut existing.name);
+            request.response_filter = std::mem::take(&mut existing.response_filter);
             *existing = request;
+            self.persist();
         }
     }
 
     pub fn delete_selected_request(&mut self) {
-        if !self.requests.is_empty() {
-            let selected = self.selected_request();
-            self.requests.remove(selected);
-
-            // Adjust editing index after deletion
-            if let Some(idx) = self.editing_request_idx {
-                if idx == selected {
-                    self.editing_request_idx = None;
-                } else if idx > selected {
-                    self.editing_request_idx = Some(idx - 1);
-                }
-            }
+        let Some(row) = self.selected_row() else { return };
+        self.collection.remove(&row.path);
 
-            if selected >= self.requests.len() && !self.requests.is_empty() {
-                self.sidebar_state.select(Some(self.requests.len() - 1));
+        // Forget requests that went away with the deleted node
+        if let Some(id) = self.editing_request
+            && self.collection.path_of(id).is_none()
+        {
+            self.editing_request = None;
+        }
+        if let Some(id) = self.moving_request
+            && self.collection.path_of(id).is_none()
+        {
+            self.moving_request = None;
+        }
+        self.exchanges.retain(|&id, exchange| {
+            let keep = self.collection.path_of(id).is_some();
+            if !keep && let Some(in_flight) = exchange.in_flight.take() {
+                in_flight.abort.abort();
             }
+            keep
+        });
+        self.refresh_response_body();
+
+        let len = self.sidebar_rows().len();
+        if self.sidebar_state.selected().unwrap_or(0) >= len && len > 0 {
+            self.sidebar_state.select(Some(len - 1));
+        }
+        self.persist();
+    }
+
+    pub fn selected_is_folder(&self) -> bool {
+        self.selected_row()
+            .is_some_and(|row| matches!(self.collection.node(&row.path), Some(Node::Folder(_))))
+    }
+
+    pub fn toggle_selected_folder(&mut self) {
+        let Some(row) = self.selected_row() else { return };
+        if let Some(Node::Folder(folder)) = self.collection.node_mut(&row.path) {
+            folder.expanded = !folder.expanded;
+            self.persist();
+        }
+    }
+
+    /// Mark the selected request to be moved, or clear an existing mark.
+    pub fn toggle_move_mark(&mut self) {
+        if self.moving_request.take().is_some() {
+            return;
+        }
+        if let Some(row) = self.selected_row()
+            && let Some(Node::Request(req)) = self.collection.node(&row.path)
+        {
+            self.moving_request = Some(req.id);
+        }
+    }
+
+    /// Move the marked request into the folder at the selection.
+    pub fn move_marked_request(&mut self) {
+        let Some(id) = self.moving_request.take() else { return };
+        let target = self.target_folder();
+        if let Some(path) = self.collection.move_request(id, &target) {
+            self.select_path(&path);
+            self.persist();
+        }
+    }
+
+    fn persist(&mut self) {
+        let Some(storage) = &self.storage else { return };
+        let workspace = WorkspaceRef {
+            collection: &self.collection,
+            environments: &self.environments,
+            active_environment: self.active_environment,
+            settings: &self.settings,
+            cookies: &self.cookies,
+        };
+        if let Err(e) = storage.save(workspace) {
+            self.show_error(format!("Failed to save history: {:#}", e));
         }
     }
 
     pub fn load_selected_request(&mut self) {
-        let idx = self.selected_request();
-        let Some(req) = self.requests.get(idx).cloned() else { return };
+        let Some(row) = self.selected_row() else { return };
+        let Some(Node::Request(req)) = self.collection.node(&row.path).cloned() else { return };
+
+        self.editing_request = Some(req.id);
+        self.response_filter = req.response_filter.clone();
+        self.refresh_response_body();
+        self.load_into_editor(*req);
+    }
 
-        self.editing_request_idx = Some(idx);
+    fn load_into_editor(&mut self, req: Request) {
         self.url_input = single_line_textarea(&req.url);
         self.method = req.method;
         self.params = req.params;
         self.headers = req.headers;
+        self.body_type = req.body_type;
+        self.form = req.form;
+        self.set_body_file(req.body_file);
+        self.set_variables(&req.variables);
         self.set_body(&req.body);
         self.auth = req.auth;
+        self.request_settings = req.settings;
         self.params_editor.reset();
         self.headers_editor.reset();
+        self.form_editor.reset();
         self.auth_editor.sync_from_auth(&self.auth);
     }
 
+    /// Replace the editor contents with a request parsed from a curl command.
+    pub fn import_curl_into_editor(&mut self, command: &str) {
+        match curl::parse(command) {
+            Ok(req) => self.load_into_editor(req),
+            Err(e) => self.show_error(format!("curl import failed: {}", e)),
+        }
+    }
+
+    /// Add a request parsed from a curl command to the sidebar and open it.
+    pub fn import_curl_as_new(&mut self, command: &str) {
+        match curl::parse(command) {
+            Ok(req) => {
+                self.add_request(req.clone());
+                self.load_into_editor(req);
+            }
+            Err(e) => self.show_error(format!("curl import failed: {}", e)),
+        }
+    }
+
+    // Prompts
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        let initial = match kind {
+            PromptKind::NewFolder
+            | PromptKind::NewEnvironment
+            | PromptKind::ImportCurl
+            | PromptKind::Search
+            | PromptKind::SearchBackward => String::new(),
+            PromptKind::HeaderFilter => self.header_filter.clone(),
+            PromptKind::ResponseFilter => self.response_filter.clone(),
+            PromptKind::EditSetting => self.setting_input(),
+            PromptKind::BodyFile => self.body_file.clone(),
+            PromptKind::SaveBody => match self.request_state() {
+                RequestState::Success(resp) => resp.suggested_file_name(),
+                _ => return,
+            },
+            PromptKind::EditCookie => match self.cookie_jar().cookies().get(self.cookie_cursor) {
+                Some(cookie) => cookie.value.clone(),
+                None => return,
+            },
+            PromptKind::RenameEnvironment => match self.environments.get(self.environment_cursor) {
+                Some(env) => env.name.clone(),
+                None => return,
+            },
+            PromptKind::Rename => match self.selected_row().and_then(|row| self.collection.node(&row.path)) {
+                Some(Node::Folder(folder)) => folder.name.clone(),
+                Some(Node::Request(req)) => req.name.clone(),
+                None => return,
+            },
+        };
+        let mut input = single_line_textarea(&initial);
+        input.move_cursor(tui_textarea::CursorMove::End);
+        self.prompt = Some(Prompt { kind, input });
+        self.edit_focus = EditFocus::Prompt;
+    }
+
+    /// Tab-complete the file path in the prompt, listing the candidates when
+    /// more than one matches.
+    pub fn complete_prompt_path(&mut self) {
+        let Some(prompt) = &mut self.prompt else { return };
+        let (completed, matches) = files::complete_path(textarea_value(&prompt.input));
+        prompt.input = single_line_textarea(&completed);
+        prompt.input.move_cursor(tui_textarea::CursorMove::End);
+        match matches.len() {
+            0 => self.show_error("No matching files"),
+            1 => {}
+            _ => self.show_info(matches.join("  ")),
+        }
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        if let Some(prompt) = self.prompt.take()
+            && prompt.kind.is_search()
+        {
+            self.search = None;
+            self.response_scroll = self.search_origin;
+        }
+        self.edit_focus = EditFocus::None;
+    }
+
+    pub fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else { return };
+        self.edit_focus = EditFocus::None;
+        let value = textarea_value(&prompt.input).trim().to_string();
+
+        match prompt.kind {
+            PromptKind::NewFolder if !value.is_empty() => {
+                self.insert_node(Node::Folder(Folder::new(value)));
+                self.persist();
+            }
+            PromptKind::NewFolder => {}
+            PromptKind::Rename => {
+                let Some(row) = self.selected_row() else { return };
+                match self.collection.node_mut(&row.path) {
+                    Some(Node::Folder(folder)) if !value.is_empty() => folder.name = value,
+                    Some(Node::Request(req)) => req.name = value,
+                    _ => return,
+                }
+                self.persist();
+            }
+            PromptKind::NewEnvironment if !value.is_empty() => {
+                self.environments.push(Environment::new(value));
+                self.environment_cursor = self.environments.len() - 1;
+                self.env_editor.reset();
+                self.persist();
+            }
+            PromptKind::RenameEnvironment if !value.is_empty() => {
+                if let Some(env) = self.environments.get_mut(self.environment_cursor) {
+                    env.name = value;
+                    self.persist();
+                }
+            }
+            PromptKind::NewEnvironment | PromptKind::RenameEnvironment => {}
+            PromptKind::ImportCurl if !value.is_empty() => self.import_curl_as_new(&value),
+            PromptKind::ImportCurl => {}
+            PromptKind::HeaderFilter => {
+                self.header_filter = value;
+                self.response_scroll = 0;
+            }
+            PromptKind::ResponseFilter => self.set_response_filter(value),
+            PromptKind::EditSetting => self.set_selected_setting(&value),
+            PromptKind::BodyFile => self.set_body_file(value),
+            PromptKind::SaveBody if !value.is_empty() => self.save_response_body(&value),
+            PromptKind::SaveBody => {}
+            PromptKind::EditCookie => {
+                let cursor = self.cookie_cursor;
+                self.cookie_jar_mut().set_value(cursor, value);
+                self.persist();
+            }
+            PromptKind::Search | PromptKind::SearchBackward if value.is_empty() => self.search = None,
+            PromptKind::Search | PromptKind::SearchBackward => {
+                match Search::new(&value, prompt.kind == PromptKind::SearchBackward) {
+                    Ok(search) => self.search = Some(search),
+                    Err(e) => return self.show_error(format!("Invalid pattern: {}", e)),
+                }
+                self.run_search(self.search_origin);
+                if self.search.as_ref().is_some_and(|s| s.matches().is_empty()) {
+                    self.show_error(format!("Pattern not found: {}", value));
+                }
+            }
+        }
+    }
+
+    // Client settings
+    pub fn toggle_settings(&mut self) {
+        self.show_settings = !self.show_settings;
+        self.settings_cursor = 0;
+    }
+
+    pub fn switch_settings_scope(&mut self) {
+        self.settings_scope = match self.settings_scope {
+            SettingsScope::Global => SettingsScope::Request,
+            SettingsScope::Request => SettingsScope::Global,
+        };
+    }
+
+    pub fn settings_select(&mut self, delta: isize) {
+        let len = Setting::ALL.len() as isize;
+        self.settings_cursor = (self.settings_cursor as isize + delta).rem_euclid(len) as usize;
+    }
+
+    pub fn selected_setting(&self) -> Setting {
+        Setting::ALL[self.settings_cursor]
+    }
+
+    /// Global settings with the open request's overrides applied.
+    pub fn effective_settings(&self) -> Settings {
+        self.settings.resolve(&self.request_settings)
+    }
+
+    /// Current text of the selected setting, for the edit prompt.
+    fn setting_input(&self) -> String {
+        let setting = self.selected_setting();
+        match self.settings_scope {
+            SettingsScope::Global => self.settings.raw(setting),
+            SettingsScope::Request => self.effective_settings().raw(setting),
+        }
+    }
+
+    fn set_selected_setting(&mut self, value: &str) {
+        let setting = self.selected_setting();
+        let result = match self.settings_scope {
+            SettingsScope::Global => self.settings.set(setting, value),
+            SettingsScope::Request => self.request_settings.set(&self.settings, setting, value),
+        };
+        match result {
+            Ok(()) if self.settings_scope == SettingsScope::Global => self.persist(),
+            Ok(()) => {}
+            Err(e) => self.show_error(format!("{}: {}", setting.label(), e)),
+        }
+    }
+
+    /// Flip an on/off setting.
+    pub fn toggle_selected_setting(&mut self) {
+        let setting = self.selected_setting();
+        if !setting.is_toggle() {
+            return;
+        }
+        let mut settings = match self.settings_scope {
+            SettingsScope::Global => self.settings.clone(),
+            SettingsScope::Request => self.effective_settings(),
+        };
+        settings.toggle(setting);
+        self.set_selected_setting(&settings.raw(setting));
+    }
+
+    /// Drop the request's override, or restore the global default.
+    pub fn reset_selected_setting(&mut self) {
+        let setting = self.selected_setting();
+        match self.settings_scope {
+            SettingsScope::Global => {
+                let _ = self.settings.set(setting, &Settings::default().raw(setting));
+                self.persist();
+            }
+            SettingsScope::Request => self.request_settings.clear(setting),
+        }
+    }
+
+    // Cookies
+    /// The active environment's jar, or the workspace one with none active.
+    pub fn cookie_jar(&self) -> &CookieJar {
+        match self.active_environment.and_then(|i| self.environments.get(i)) {
+            Some(env) => &env.cookies,
+            None => &self.cookies,
+        }
+    }
+
+    fn cookie_jar_mut(&mut self) -> &mut CookieJar {
+        match self.active_environment.and_then(|i| self.environments.get_mut(i)) {
+            Some(env) => &mut env.cookies,
+            None => &mut self.cookies,
+        }
+    }
+
+    /// Keep the cookies a response from `url` set, in the jar of the
+    /// environment the request was sent in, even if another is active now.
+    fn store_cookies<'s>(
+        &mut self,
+        environment: Option<EnvironmentId>,
+        url: &str,
+        set_cookies: impl Iterator<Item = &'s str>,
+    ) {
+        let Ok(url) = reqwest::Url::parse(url) else { return };
+        let Some(host) = url.host_str() else { return };
+        let jar = match environment {
+            // Its environment may have been deleted since
+            Some(id) => match self.environments.iter_mut().find(|env| env.id == id) {
+                Some(env) => &mut env.cookies,
+                None => return,
+            },
+            None => &mut self.cookies,
+        };
+        let mut any = false;
+        jar.store(host, url.path(), set_cookies.inspect(|_| any = true));
+        if any {
+            self.persist();
+        }
+    }
+
+    pub fn toggle_cookies(&mut self) {
+        self.show_cookies = !self.show_cookies;
+        self.cookie_cursor = 0;
+    }
+
+    pub fn cookies_select(&mut self, delta: isize) {
+        let len = self.cookie_jar().cookies().len() as isize;
+        if len > 0 {
+            self.cookie_cursor = (self.cookie_cursor as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    pub fn delete_selected_cookie(&mut self) {
+        let cursor = self.cookie_cursor;
+        self.cookie_jar_mut().remove(cursor);
+        self.clamp_cookie_cursor();
+        self.persist();
+    }
+
+    /// Delete every cookie of the selected cookie's domain.
+    pub fn clear_selected_cookie_domain(&mut self) {
+        let Some(domain) = self.cookie_jar().cookies().get(self.cookie_cursor).map(|c| c.domain.clone()) else {
+            return;
+        };
+        self.cookie_jar_mut().clear_domain(&domain);
+        self.clamp_cookie_cursor();
+        self.persist();
+    }
+
+    pub fn clear_cookies(&mut self) {
+        self.cookie_jar_mut().clear();
+        self.cookie_cursor = 0;
+        self.persist();
+    }
+
+    fn clamp_cookie_cursor(&mut self) {
+        let len = self.cookie_jar().cookies().len();
+        self.cookie_cursor = self.cookie_cursor.min(len.saturating_sub(1));
+    }
+
+    // Environments
+    pub fn active_variables(&self) -> &[KeyValue] {
+        self.active_environment
+            .and_then(|i| self.environments.get(i))
+            .map(|env| env.variables.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn active_environment_name(&self) -> Option<&str> {
+        self.active_environment
+            .and_then(|i| self.environments.get(i))
+            .map(|env| env.name.as_str())
+    }
+
+    /// Step the active environment through every environment, then none.
+    pub fn cycle_active_environment(&mut self) {
+        self.active_environment = match self.active_environment {
+            None if !self.environments.is_empty() => Some(0),
+            Some(i) if i + 1 < self.environments.len() => Some(i + 1),
+            _ => None,
+        };
+        self.persist();
+    }
+
+    pub fn toggle_environments(&mut self) {
+        self.show_environments = !self.show_environments;
+        if self.show_environments {
+            self.environment_cursor = self.active_environment.unwrap_or(0);
+            self.env_editor.reset();
+        }
+    }
+
+    fn is_editing_environment(&self) -> bool {
+        self.show_environments && self.environment_cursor < self.environments.len()
+    }
+
+    pub fn environment_next(&mut self) {
+        if !self.environments.is_empty() {
+            self.environment_cursor = (self.environment_cursor + 1) % self.environments.len();
+            self.env_editor.reset();
+        }
+    }
+
+    pub fn environment_prev(&mut self) {
+        if !self.environments.is_empty() {
+            let len = self.environments.len();
+            self.environment_cursor = (self.environment_cursor + len - 1) % len;
+            self.env_editor.reset();
+        }
+    }
+
+    /// Make the environment under the cursor active, or deactivate it if it already is.
+    pub fn activate_cursor_environment(&mut self) {
+        if self.environment_cursor >= self.environments.len() {
+            return;
+        }
+        self.active_environment = if self.active_environment == Some(self.environment_cursor) {
+            None
+        } else {
+            Some(self.environment_cursor)
+        };
+        self.persist();
+    }
+
+    pub fn delete_cursor_environment(&mut self) {
+        let idx = self.environment_cursor;
+        if idx >= self.environments.len() {
+            return;
+        }
+        self.environments.remove(idx);
+
+        self.active_environment = match self.active_environment {
+            Some(i) if i == idx => None,
+            Some(i) if i > idx => Some(i - 1),
+            other => other,
+        };
+        if self.environment_cursor >= self.environments.len() {
+            self.environment_cursor = self.environments.len().saturating_sub(1);
+        }
+        self.env_editor.reset();
+        self.persist();
+    }
+
     // Editing
     pub fn start_editing(&mut self, focus: EditFocus) {
         self.edit_focus = focus;
@@ -443,6 +1326,7 @@ impl<'a> App<'a> {
         }
         if self.edit_focus == EditFocus::Body {
             self.validate_json();
+            self.refresh_body_preview();
         }
         if self.edit_focus == EditFocus::Auth {
             self.sync_auth_from_editor();
@@ -462,15 +1346,114 @@ impl<'a> App<'a> {
     }
 
     pub fn sync_auth_from_editor(&mut self) {
-        let first = textarea_value(&self.auth_editor.first_input).to_string();
-        let second = textarea_value(&self.auth_editor.second_input).to_string();
-
-        self.auth = match &self.auth {
-            AuthType::None => AuthType::None,
-            AuthType::Basic { .. } => AuthType::Basic { username: first, password: second },
-            AuthType::Bearer { .. } => AuthType::Bearer { token: first },
-            AuthType::ApiKey { .. } => AuthType::ApiKey { key: first, value: second },
+        let values = self.auth_editor.inputs.iter().map(|input| textarea_value(input).to_string()).collect();
+        self.auth.set_fields(values);
+    }
+
+    /// The cached token for the editor's OAuth2 auth, if any.
+    pub fn oauth_token(&self) -> Option<&OAuthToken> {
+        let mut vars = template::Expander::new(self.active_variables());
+        let AuthType::OAuth2(config) = vars.auth(&self.auth) else { return None };
+        self.oauth_tokens.get(&config)
+    }
+
+    /// Drop the cached token, so the next send gets a new one.
+    pub fn forget_oauth_token(&mut self) {
+        let mut vars = template::Expander::new(self.active_variables());
+        if let AuthType::OAuth2(config) = vars.auth(&self.auth)
+            && self.oauth_tokens.remove(&config).is_some()
+        {
+            self.show_info("Access token forgotten");
+        }
+    }
+
+    /// Switch an OAuth2 auth to its next grant type, or an API key to the
+    /// next place it is sent.
+    pub fn cycle_auth_option(&mut self) {
+        match &mut self.auth {
+            AuthType::OAuth2(config) => config.grant = config.grant.next(),
+            AuthType::ApiKey { location, .. } => *location = location.next(),
+            _ => return,
+        }
+        self.auth_editor.sync_from_auth(&self.auth);
+    }
+
+    pub fn cycle_body_type(&mut self, forward: bool) {
+        self.body_type = if forward { self.body_type.next() } else { self.body_type.prev() };
+        self.form_editor.reset();
+        self.validate_json();
+        self.refresh_body_preview();
+    }
+
+    /// Switch between the GraphQL query and variables editors.
+    pub fn toggle_variables_editor(&mut self) {
+        self.editing_variables = !self.editing_variables;
+        self.validate_json();
+        self.refresh_body_preview();
+    }
+
+    /// The editor `e` opens in the Body tab.
+    pub fn active_body_editor(&mut self) -> &mut TextArea<'a> {
+        if self.body_type == BodyType::GraphQl && self.editing_variables {
+            &mut self.variables_editor
+        } else {
+            &mut self.body_editor
+        }
+    }
+
+    /// The schema introspected from the open request's endpoint. Looked up
+    /// on every draw, so only the URL is expanded, not the whole request.
+    fn schema(&self) -> Option<&Schema> {
+        let mut vars = template::Expander::new(self.active_variables());
+        let url = vars.text(self.url().trim());
+        vars.finish().ok()?;
+        self.schemas.get(&url)
+    }
+
+    pub fn set_schema(&mut self, url: String, schema: Result<Schema, String>) {
+        match schema {
+            Ok(schema) => {
+                self.show_info(format!("Loaded GraphQL schema: {} types", schema.type_count()));
+                self.schemas.insert(url, schema);
+            }
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    pub fn has_schema(&self) -> bool {
+        self.schema().is_some()
+    }
+
+    /// Complete the field name before the cursor in the GraphQL query from
+    /// the schema, listing the candidates when more than one matches.
+    pub fn complete_query(&mut self) {
+        let Some(schema) = self.schema() else {
+            return self.show_error("No schema for this URL yet; press Ctrl+G to fetch it");
         };
+        let (row, col) = self.body_editor.cursor();
+        let lines = self.body_editor.lines();
+        let mut before = lines[..row].join("\n");
+        if row > 0 {
+            before.push('\n');
+        }
+        before.extend(lines[row].chars().take(col));
+
+        let (prefix, fields) = schema.complete(&before);
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        let rest = common_prefix(&names).get(prefix.len()..).unwrap_or("").to_string();
+        let candidates: Vec<String> = fields.iter().map(|f| format!("{}: {}", f.name, f.type_name)).collect();
+
+        self.body_editor.insert_str(rest);
+        match candidates.len() {
+            0 => self.show_error("No matching fields"),
+            1 => {}
+            _ => self.show_info(candidates.join("  ")),
+        }
+    }
+
+    /// Whether the active tab is edited as a key-value list.
+    pub fn tab_is_kv(&self) -> bool {
+        self.active_tab != RequestTab::Body || self.body_type.is_form()
     }
 
     pub fn cycle_method_next(&mut self) {
@@ -483,29 +1466,45 @@ impl<'a> App<'a> {
 
     // Key-value helpers
     pub fn current_kv_items(&self) -> &Vec<KeyValue> {
+        if self.is_editing_environment() {
+            return &self.environments[self.environment_cursor].variables;
+        }
         match self.active_tab {
             RequestTab::Params => &self.params,
+            RequestTab::Body if self.body_type.is_form() => &self.form,
             RequestTab::Headers | RequestTab::Body | RequestTab::Auth => &self.headers,
         }
     }
 
     fn current_kv_items_mut(&mut self) -> &mut Vec<KeyValue> {
+        if self.is_editing_environment() {
+            return &mut self.environments[self.environment_cursor].variables;
+        }
         match self.active_tab {
             RequestTab::Params => &mut self.params,
+            RequestTab::Body if self.body_type.is_form() => &mut self.form,
             RequestTab::Headers | RequestTab::Body | RequestTab::Auth => &mut self.headers,
         }
     }
 
     pub fn current_kv_editor(&self) -> &KvEditor {
+        if self.show_environments {
+            return &self.env_editor;
+        }
         match self.active_tab {
             RequestTab::Params => &self.params_editor,
+            RequestTab::Body if self.body_type.is_form() => &self.form_editor,
             RequestTab::Headers | RequestTab::Body | RequestTab::Auth => &self.headers_editor,
         }
     }
 
     pub fn current_kv_editor_mut(&mut self) -> &mut KvEditor {
+        if self.show_environments {
+            return &mut self.env_editor;
+        }
         match self.active_tab {
             RequestTab::Params => &mut self.params_editor,
+            RequestTab::Body if self.body_type.is_form() => &mut self.form_editor,
             RequestTab::Headers | RequestTab::Body | RequestTab::Auth => &mut self.headers_editor,
         }
     }
@@ -530,6 +1529,14 @@ impl<'a> App<'a> {
             item.key = key;
             item.value = value;
         }
+        self.persist_environment_edit();
+    }
+
+    /// Environment variables are saved as they're edited, like requests are on send.
+    fn persist_environment_edit(&mut self) {
+        if self.is_editing_environment() {
+            self.persist();
+        }
     }
 
     pub fn kv_add(&mut self) {
@@ -554,6 +1561,7 @@ impl<'a> App<'a> {
         if editor.selected() >= new_len && new_len > 0 {
             editor.select(new_len - 1);
         }
+        self.persist_environment_edit();
     }
 
     pub fn kv_toggle_enabled(&mut self) {
@@ -561,6 +1569,7 @@ impl<'a> App<'a> {
         if let Some(item) = self.current_kv_items_mut().get_mut(selected) {
             item.enabled = !item.enabled;
         }
+        self.persist_environment_edit();
     }
 
     fn kv_navigate(&mut self, forward: bool) {
@@ -592,28 +1601,430 @@ impl<'a> App<'a> {
     }
 
     // Request state
-    pub fn set_loading(&mut self) {
-        self.request_state = RequestState::Loading;
-        self.response_scroll = 0;
+    /// State of the request open in the editor.
+    pub fn request_state(&self) -> &RequestState {
+        self.editing_request
+            .and_then(|id| self.exchanges.get(&id))
+            .map_or(&IDLE, |exchange| &exchange.state)
+    }
+
+    /// When `id`'s send started, if it is still in flight.
+    pub fn loading_since(&self, id: RequestId) -> Option<Instant> {
+        self.exchanges.get(&id)?.in_flight.as_ref().map(|f| f.started)
+    }
+
+    /// Body bytes received so far by the open request's send, the expected
+    /// total, and how long it has been running.
+    pub fn download_progress(&self) -> Option<(u64, Option<u64>, Duration)> {
+        let in_flight = self.exchanges.get(&self.editing_request?)?.in_flight.as_ref()?;
+        Some((in_flight.received, in_flight.total, in_flight.started.elapsed()))
+    }
+
+    /// What the open request's send is waiting for, before it goes out.
+    pub fn loading_status(&self) -> Option<&str> {
+        let in_flight = self.exchanges.get(&self.editing_request?)?.in_flight.as_ref()?;
+        in_flight.status.as_deref()
+    }
+
+    /// Tag a new send of the open request; `None` if it has not been saved.
+    pub fn next_ticket(&mut self) -> Option<Ticket> {
+        let request = self.editing_request?;
+        self.request_seq += 1;
+        let environment = self.active_environment.and_then(|i| self.environments.get(i)).map(|env| env.id);
+        Some(Ticket { request, seq: self.request_seq, environment })
+    }
+
+    pub fn set_loading(&mut self, ticket: Ticket, abort: AbortHandle, socket: Option<UnboundedSender<WsCommand>>) {
+        let exchange = self.exchanges.entry(ticket.request).or_default();
+        exchange.in_flight = Some(InFlight {
+            seq: ticket.seq,
+            abort,
+            started: Instant::now(),
+            received: 0,
+            total: None,
+            socket,
+            status: None,
+        });
+        exchange.state = RequestState::Loading;
+        if self.editing_request == Some(ticket.request) {
+            self.refresh_response_body();
+        }
     }
 
-    pub fn set_response(&mut self, response: Response) {
-        self.request_state = RequestState::Success(response);
-        self.response_scroll = 0;
+    /// Route a send's progress or result to its request, dropping those of
+    /// cancelled or superseded sends.
+    pub fn handle_http_result(&mut self, ticket: Ticket, result: HttpResult) {
+        // Worth keeping even if the send they came with has been cancelled
+        let result = match result {
+            HttpResult::Token { config, token } => {
+                self.oauth_tokens.insert(config, token);
+                return;
+            }
+            HttpResult::Cookies { url, set_cookies } => {
+                self.store_cookies(ticket.environment, &url, set_cookies.iter().map(String::as_str));
+                return;
+            }
+            result => result,
+        };
+        let Some(exchange) = self.exchanges.get_mut(&ticket.request) else { return };
+        let Some(in_flight) = exchange.in_flight.as_mut().filter(|f| f.seq == ticket.seq) else {
+            return;
+        };
+        let state = match result {
+            HttpResult::Progress { received, total } => {
+                in_flight.received = received;
+                in_flight.total = total;
+                return;
+            }
+            HttpResult::Status(status) => {
+                in_flight.status = Some(status);
+                return;
+            }
+            HttpResult::Token { .. } | HttpResult::Cookies { .. } => unreachable!("handled above"),
+            HttpResult::Event(event) => return self.push_event(ticket.request, event),
+            HttpResult::Closed { received, error } => {
+                let elapsed = in_flight.started.elapsed();
+                exchange.in_flight = None;
+                let reason = match error {
+                    Some(e) => format!("Stream failed: {}", e),
+                    None => "Connection closed".to_string(),
+                };
+                return self.end_event_stream(ticket.request, elapsed, received, reason);
+            }
+            // Stays in flight while events arrive
+            HttpResult::Stream(response) => RequestState::Success(Box::new(response)),
+            HttpResult::Success(response) => {
+                exchange.in_flight = None;
+                RequestState::Success(Box::new(response))
+            }
+            HttpResult::Error(err) => {
+                exchange.in_flight = None;
+                RequestState::Error(err)
+            }
+        };
+
+        if let RequestState::Success(response) = &state {
+            let set_cookies = response
+                .headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+                .map(|(_, value)| value.as_str());
+            self.store_cookies(ticket.environment, &response.url, set_cookies);
+        }
+        self.exchanges.entry(ticket.request).or_default().state = state;
+        if self.editing_request == Some(ticket.request) {
+            self.refresh_response_body();
+        }
+    }
+
+    pub fn cancel_request(&mut self) {
+        let Some(id) = self.editing_request else { return };
+        let Some(exchange) = self.exchanges.get_mut(&id) else { return };
+        // A socket is closed properly first; cancelling again drops it
+        if let Some(socket) = exchange.in_flight.as_mut().and_then(|f| f.socket.take()) {
+            let _ = socket.send(WsCommand::Close);
+            return;
+        }
+        let Some(in_flight) = exchange.in_flight.take() else { return };
+        in_flight.abort.abort();
+        // Stopping an event stream keeps the events received so far
+        if let RequestState::Success(resp) = &exchange.state
+            && resp.events.is_some()
+        {
+            let elapsed = in_flight.started.elapsed();
+            return self.end_event_stream(id, elapsed, in_flight.received, "Stopped".to_string());
+        }
+        exchange.state = RequestState::Cancelled;
+        self.refresh_response_body();
+    }
+
+    /// The open request's WebSocket, while it is connected.
+    fn socket(&self) -> Option<&UnboundedSender<WsCommand>> {
+        self.exchanges.get(&self.editing_request?)?.in_flight.as_ref()?.socket.as_ref()
+    }
+
+    pub fn socket_open(&self) -> bool {
+        self.socket().is_some()
     }
 
+    /// Send the body editor's text as a message on the open WebSocket.
+    pub fn send_socket_message(&mut self) {
+        let text = match self.request_data() {
+            Ok(data) => data.body,
+            Err(e) => return self.show_error(e),
+        };
+        if let Some(socket) = self.socket() {
+            let _ = socket.send(WsCommand::Send(text));
+        }
+    }
+
+    pub fn ping_socket(&self) {
+        if let Some(socket) = self.socket() {
+            let _ = socket.send(WsCommand::Ping);
+        }
+    }
+
+    /// Append an event to `id`'s event log.
+    fn push_event(&mut self, id: RequestId, event: StreamEvent) {
+        let Some(exchange) = self.exchanges.get_mut(&id) else { return };
+        let RequestState::Success(resp) = &mut exchange.state else { return };
+        let Some(log) = &mut resp.events else { return };
+        let lines = highlight::event_lines(&event);
+        log.events.push(event);
+        if self.editing_request == Some(id) {
+            self.response_lines.extend(lines);
+        }
+    }
+
+    /// Record that `id`'s event stream ended, after `elapsed` and `received`
+    /// bytes, and why.
+    fn end_event_stream(&mut self, id: RequestId, elapsed: Duration, received: u64, reason: String) {
+        let Some(exchange) = self.exchanges.get_mut(&id) else { return };
+        let RequestState::Success(resp) = &mut exchange.state else { return };
+        let Some(log) = &mut resp.events else { return };
+        let line = highlight::stream_end_line(&reason);
+        log.ended = Some(reason);
+        resp.elapsed = elapsed;
+        resp.size_bytes = received as usize;
+        if self.editing_request == Some(id) {
+            self.response_lines.push(line);
+        }
+    }
+
+    /// Show an error for the open request, e.g. when it can't be sent at all.
     pub fn set_error(&mut self, error: String) {
-        self.request_state = RequestState::Error(error);
+        let Some(id) = self.editing_request else { return self.show_error(error) };
+        self.exchanges.entry(id).or_default().state = RequestState::Error(error);
+        self.refresh_response_body();
+    }
+
+    /// Rebuild the highlighted body and JSON tree, running the jq filter if set.
+    fn refresh_response_body(&mut self) {
         self.response_scroll = 0;
+        self.follow_events = true;
+        self.filter_error = None;
+        let RequestState::Success(resp) = self.request_state() else {
+            self.response_lines.clear();
+            self.json_tree = None;
+            return;
+        };
+
+        if let Some(log) = &resp.events {
+            let mut lines: Vec<Line<'static>> = log.events.iter().flat_map(highlight::event_lines).collect();
+            lines.extend(log.ended.as_deref().map(highlight::stream_end_line));
+            self.response_lines = lines;
+            self.json_tree = None;
+            self.run_search(0);
+            return;
+        }
+
+        if self.hex_view || resp.is_binary() {
+            self.response_lines = binary::hex_dump(resp.bytes());
+            self.json_tree = None;
+            self.run_search(0);
+            return;
+        }
+
+        let mut body = resp.formatted_body();
+        let mut syntax = Syntax::detect(resp.content_type(), &body);
+        let mut filter_error = None;
+        // Only the start of a spilled body is held; a filter or tree over it would mislead
+        let whole = resp.spill.is_none();
+        if !self.response_filter.is_empty() && !whole {
+            filter_error = Some("not applied to a truncated body".to_string());
+        } else if !self.response_filter.is_empty() {
+            match jq::apply(&self.response_filter, &resp.body) {
+                Ok(output) => {
+                    body = output;
+                    syntax = Syntax::Json;
+                }
+                Err(e) => filter_error = Some(e),
+            }
+        }
+
+        // Colouring a huge body costs more than it helps
+        if body.len() > HIGHLIGHT_MAX_BYTES {
+            syntax = Syntax::Plain;
+        }
+
+        self.filter_error = filter_error;
+        self.response_lines = highlight::highlight(&body, syntax);
+        self.json_tree = if whole { JsonTree::parse(&body) } else { None };
+        self.run_search(0);
+    }
+
+    pub fn hex_view_active(&self) -> bool {
+        match self.request_state() {
+            RequestState::Success(resp) => resp.events.is_none() && (self.hex_view || resp.is_binary()),
+            _ => false,
+        }
+    }
+
+    pub fn toggle_hex_view(&mut self) {
+        if let RequestState::Success(resp) = self.request_state()
+            && resp.is_binary()
+        {
+            return self.show_error("Binary bodies are always shown as hex");
+        }
+        self.hex_view = !self.hex_view;
+        self.refresh_response_body();
+    }
+
+    /// Write the response body, byte for byte, to `path`.
+    fn save_response_body(&mut self, path: &str) {
+        let RequestState::Success(resp) = self.request_state() else { return };
+        let target = files::expand_home(path);
+        let saved = match &resp.spill {
+            Some(spill) => std::fs::copy(&spill.path, &target).map(|_| ()),
+            None => std::fs::write(&target, resp.bytes()),
+        };
+        match saved {
+            Ok(()) => self.show_info(format!("Saved {} to {}", resp.size_display(), target.display())),
+            Err(e) => self.show_error(format!("Failed to save {}: {}", target.display(), e)),
+        }
+    }
+
+    pub fn set_response_filter(&mut self, filter: String) {
+        self.response_filter = filter;
+        self.refresh_response_body();
+
+        if let Some(id) = self.editing_request
+            && let Some(req) = self.collection.find_request_mut(id)
+        {
+            req.response_filter = self.response_filter.clone();
+            self.persist();
+        }
     }
 
+    /// Whether the open request has a send in flight, including an event
+    /// stream that is still open.
     pub fn is_loading(&self) -> bool {
-        matches!(self.request_state, RequestState::Loading)
+        self.editing_request
+            .and_then(|id| self.exchanges.get(&id))
+            .is_some_and(|exchange| exchange.in_flight.is_some())
+    }
+
+    /// Whether the open request is showing an event stream that is still open.
+    pub fn is_streaming(&self) -> bool {
+        self.is_loading() && matches!(self.request_state(), RequestState::Success(_))
+    }
+
+    /// Keep the newest events in view of a `height`-line body, unless the
+    /// user has scrolled up; scrolling back to the end follows again.
+    pub fn follow_events(&mut self, height: usize) {
+        let bottom = self.response_lines.len().saturating_sub(height);
+        if self.response_scroll >= bottom {
+            self.follow_events = true;
+        }
+        if self.follow_events {
+            self.response_scroll = bottom;
+        }
+    }
+
+    pub fn set_response_tab(&mut self, tab: ResponseTab) {
+        self.response_tab = tab;
+        self.response_scroll = 0;
+    }
+
+    // Response search
+    pub fn start_search(&mut self, backward: bool) {
+        self.tree_view = false;
+        self.search_origin = self.response_scroll;
+        self.open_prompt(if backward { PromptKind::SearchBackward } else { PromptKind::Search });
+    }
+
+    /// Re-run the search as the pattern is typed, jumping to the first hit.
+    pub fn update_search(&mut self) {
+        let Some(prompt) = &self.prompt else { return };
+        let pattern = textarea_value(&prompt.input);
+        let backward = prompt.kind == PromptKind::SearchBackward;
+
+        self.response_scroll = self.search_origin;
+        self.search = Search::new(pattern, backward).ok().filter(|_| !pattern.is_empty());
+        self.run_search(self.search_origin);
+    }
+
+    fn run_search(&mut self, from_line: usize) {
+        let Some(search) = self.search.as_mut() else { return };
+        let lines: Vec<String> = self.response_lines.iter().map(highlight::plain_text).collect();
+        search.run(lines.iter().map(String::as_str), from_line);
+        if let Some(hit) = search.current() {
+            self.scroll_to_line(hit.line);
+        }
+    }
+
+    /// `n`/`N`: step through hits, in the search direction unless `reverse`.
+    pub fn search_next(&mut self, reverse: bool) {
+        let Some(search) = self.search.as_mut() else {
+            return self.show_error("No previous search");
+        };
+        match search.step(reverse) {
+            Some(hit) => self.scroll_to_line(hit.line),
+            None => {
+                let pattern = search.pattern.clone();
+                self.show_error(format!("Pattern not found: {}", pattern));
+            }
+        }
+    }
+
+    /// Scroll so `line` sits a few lines below the top of the body.
+    fn scroll_to_line(&mut self, line: usize) {
+        self.response_tab = ResponseTab::Body;
+        self.tree_view = false;
+        self.response_scroll = line.saturating_sub(SEARCH_CONTEXT_LINES);
+    }
+
+    /// Whether the body tab is showing the JSON tree rather than text.
+    pub fn tree_view_active(&self) -> bool {
+        self.tree_view
+            && self.response_tab == ResponseTab::Body
+            && self.json_tree.is_some()
+            && matches!(self.request_state(), RequestState::Success(_))
+    }
+
+    pub fn toggle_tree_view(&mut self) {
+        if !self.tree_view && self.json_tree.is_none() {
+            let spilled = matches!(self.request_state(), RequestState::Success(resp) if resp.spill.is_some());
+            if spilled {
+                self.show_error("Tree view needs the whole body; this one was too large to keep");
+            } else {
+                self.show_error("Response body is not a JSON object or array");
+            }
+            return;
+        }
+        self.tree_view = !self.tree_view;
+        self.fold_pending = false;
+    }
+
+    /// Response headers matching the header filter (case-insensitive, name or value).
+    pub fn visible_headers(&self) -> Vec<&(String, String)> {
+        let RequestState::Success(resp) = self.request_state() else { return vec![] };
+        let filter = self.header_filter.to_lowercase();
+        resp.headers
+            .iter()
+            .filter(|(name, value)| {
+                filter.is_empty()
+                    || name.to_lowercase().contains(&filter)
+                    || value.to_lowercase().contains(&filter)
+            })
+            .collect()
+    }
+
+    /// Number of scrollable lines in the active response tab.
+    pub fn response_line_count(&self) -> usize {
+        let RequestState::Success(resp) = self.request_state() else { return 0 };
+        match self.response_tab {
+            ResponseTab::Body => self.response_lines.len(),
+            ResponseTab::Headers => self.visible_headers().len(),
+            ResponseTab::Cookies => resp.cookies().len() * 2,
+            ResponseTab::Timing => 0,
+        }
     }
 
     // Response scrolling
     pub fn response_scroll_up(&mut self) {
         scroll_by(&mut self.response_scroll, -1, usize::MAX);
+        self.follow_events = false;
     }
 
     pub fn response_scroll_down(&mut self) {
@@ -622,6 +2033,7 @@ impl<'a> App<'a> {
 
     pub fn response_scroll_top(&mut self) {
         self.response_scroll = 0;
+        self.follow_events = false;
     }
 
     pub fn response_scroll_bottom(&mut self, max: usize) {
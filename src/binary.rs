@@ -0,0 +1,99 @@
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+
+use crate::ui::theme;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Dumps stop here; a line for every 16 bytes of a large body is too many to
+/// rebuild on each refresh.
+pub const HEX_MAX_BYTES: usize = 1024 * 1024;
+
+/// `xxd`-style dump of up to `HEX_MAX_BYTES`: offset, hex bytes in two groups
+/// of eight, printable ASCII.
+pub fn hex_dump(bytes: &[u8]) -> Vec<Line<'static>> {
+    bytes[..bytes.len().min(HEX_MAX_BYTES)]
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut hex = String::with_capacity(BYTES_PER_LINE * 3 + 1);
+            for (j, byte) in chunk.iter().enumerate() {
+                if j == BYTES_PER_LINE / 2 {
+                    hex.push(' ');
+                }
+                hex.push_str(&format!("{:02x} ", byte));
+            }
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+
+            Line::from(vec![
+                Span::styled(format!("{:08x}  ", i * BYTES_PER_LINE), Style::default().fg(theme::TEXT_DIM)),
+                Span::styled(format!("{:<50}", hex), Style::default().fg(theme::TEXT)),
+                Span::styled(format!("|{}|", ascii), Style::default().fg(theme::SYNTAX_STRING)),
+            ])
+        })
+        .collect()
+}
+
+/// An image recognised from its leading bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub format: &'static str,
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl ImageInfo {
+    /// `PNG image, 800×600`
+    pub fn describe(&self) -> String {
+        match self.dimensions {
+            Some((w, h)) => format!("{} image, {}×{}", self.format, w, h),
+            None => format!("{} image", self.format),
+        }
+    }
+}
+
+/// Recognise PNG, JPEG, GIF, WebP, BMP and ICO images by their signature.
+pub fn detect_image(bytes: &[u8]) -> Option<ImageInfo> {
+    let be32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let le16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+    let le32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    let (format, dimensions) = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        ("PNG", be32(16).zip(be32(20)))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        ("GIF", le16(6).zip(le16(8)))
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        ("JPEG", jpeg_dimensions(bytes))
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        ("WebP", None)
+    } else if bytes.starts_with(b"BM") && bytes.len() > 26 {
+        ("BMP", le32(18).zip(le32(22).map(|h| (h as i32).unsigned_abs())))
+    } else if bytes.starts_with(&[0, 0, 1, 0]) {
+        ("ICO", None)
+    } else {
+        return None;
+    };
+    Some(ImageInfo { format, dimensions })
+}
+
+/// Width and height from the first start-of-frame marker.
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    while i + 9 < bytes.len() {
+        if bytes[i] != 0xff {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        let len = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+        // SOF0..SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let h = u16::from_be_bytes([bytes[i + 5], bytes[i + 6]]) as u32;
+            let w = u16::from_be_bytes([bytes[i + 7], bytes[i + 8]]) as u32;
+            return Some((w, h));
+        }
+        i += 2 + len;
+    }
+    None
+}
@@ -0,0 +1,393 @@
+use crate::http::{self, RequestData};
+use crate::models::{AuthType, BodyType, HttpMethod};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    Curl,
+    Httpie,
+    Python,
+    JavaScript,
+    Rust,
+}
+
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Language::Curl,
+        Language::Httpie,
+        Language::Python,
+        Language::JavaScript,
+        Language::Rust,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Curl => "curl",
+            Language::Httpie => "HTTPie",
+            Language::Python => "Python",
+            Language::JavaScript => "JavaScript",
+            Language::Rust => "Rust",
+        }
+    }
+
+    /// How a line comment starts.
+    fn comment(self) -> &'static str {
+        match self {
+            Language::Curl | Language::Httpie | Language::Python => "#",
+            Language::JavaScript | Language::Rust => "//",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&l| l == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|&l| l == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// The request exactly as `execute_request` would send it.
+struct Prepared {
+    method: &'static str,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    /// Multipart fields; a value of `@path` is a file.
+    multipart: Vec<(String, String)>,
+    /// Path of a file sent as the body.
+    body_file: Option<String>,
+    /// Auth that isn't a plain header, for targets that handle it themselves.
+    auth: AuthType,
+}
+
+impl Prepared {
+    fn new(data: &RequestData) -> Self {
+        let mut headers = http::request_headers(data);
+        // A fresh token would have been sent as a bearer token already
+        if let AuthType::OAuth2(_) = data.auth {
+            headers.insert(0, ("Authorization".to_string(), "Bearer <token>".to_string()));
+        }
+        // A WebSocket's body is its first message, not part of the handshake
+        if data.method != HttpMethod::Ws
+            && let Some(content_type) = http::default_content_type(data)
+        {
+            headers.push(("Content-Type".to_string(), content_type.to_string()));
+        }
+
+        Self {
+            method: data.method.name(),
+            url: http::request_url(data),
+            headers,
+            body: http::text_body(data),
+            multipart: match data.body_type {
+                BodyType::Multipart => http::form_fields(&data.form)
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+                _ => vec![],
+            },
+            body_file: (data.body_type == BodyType::File && !data.body_file.is_empty())
+                .then(|| data.body_file.clone()),
+            auth: data.auth.clone(),
+        }
+    }
+}
+
+pub fn generate(language: Language, data: &RequestData) -> String {
+    let req = Prepared::new(data);
+    let code = if data.method == HttpMethod::Ws {
+        websocket(language, &req)
+    } else {
+        match language {
+            Language::Curl => curl(&req),
+            Language::Httpie => httpie(&req),
+            Language::Python => python(&req),
+            Language::JavaScript => javascript(&req),
+            Language::Rust => rust(&req),
+        }
+    };
+    match auth_note(language, data) {
+        Some(note) => format!("{} {}\n{}", language.comment(), note, code),
+        None => code,
+    }
+}
+
+/// What the reader still has to do about auth the snippet can't carry.
+fn auth_note(language: Language, data: &RequestData) -> Option<&'static str> {
+    let socket = data.method == HttpMethod::Ws;
+    match &data.auth {
+        AuthType::AwsSigV4(_) if socket || language != Language::Curl => {
+            Some("Sign this request with AWS SigV4 before sending it; it is unsigned here")
+        }
+        AuthType::Digest { .. } if socket || matches!(language, Language::JavaScript | Language::Rust) => {
+            Some("The server expects Digest auth, which this snippet doesn't answer")
+        }
+        AuthType::OAuth2(_) => Some("Replace <token> with an OAuth2 access token; no fresh one is cached"),
+        _ => None,
+    }
+}
+
+fn curl(req: &Prepared) -> String {
+    let mut parts = vec![format!("curl -X {} {}", req.method, shell_quote(&req.url))];
+    if let AuthType::AwsSigV4(credentials) = &req.auth {
+        let provider = format!("aws:amz:{}:{}", credentials.region, credentials.service);
+        parts.push(format!("  --aws-sigv4 {}", shell_quote(&provider)));
+        let user = format!("{}:{}", credentials.access_key, credentials.secret_key);
+        parts.push(format!("  --user {}", shell_quote(&user)));
+        if !credentials.session_token.is_empty() {
+            let token = format!("X-Amz-Security-Token: {}", credentials.session_token);
+            parts.push(format!("  -H {}", shell_quote(&token)));
+        }
+    }
+    if let AuthType::Digest { username, password } = &req.auth {
+        parts.push(format!("  --digest -u {}", shell_quote(&format!("{}:{}", username, password))));
+    }
+    for (key, value) in &req.headers {
+        parts.push(format!("  -H {}", shell_quote(&format!("{}: {}", key, value))));
+    }
+    if let Some(body) = &req.body {
+        parts.push(format!("  --data-raw {}", shell_quote(body)));
+    }
+    for (key, value) in &req.multipart {
+        parts.push(format!("  -F {}", shell_quote(&format!("{}={}", key, value))));
+    }
+    if let Some(path) = &req.body_file {
+        parts.push(format!("  --data-binary {}", shell_quote(&format!("@{}", path))));
+    }
+    parts.join(" \\\n")
+}
+
+fn httpie(req: &Prepared) -> String {
+    let form = if req.multipart.is_empty() { "" } else { " --multipart" };
+    let mut parts = vec![format!("http{} {} {}", form, req.method, shell_quote(&req.url))];
+    if let AuthType::Digest { username, password } = &req.auth {
+        parts.push(format!("  -A digest -a {}", shell_quote(&format!("{}:{}", username, password))));
+    }
+    for (key, value) in &req.headers {
+        parts.push(format!("  {}", shell_quote(&format!("{}:{}", key, value))));
+    }
+    if let Some(body) = &req.body {
+        parts.push(format!("  --raw {}", shell_quote(body)));
+    }
+    for (key, value) in &req.multipart {
+        let field = match value.strip_prefix('@') {
+            Some(path) => format!("{}@{}", key, path),
+            None => format!("{}={}", key, value),
+        };
+        parts.push(format!("  {}", shell_quote(&field)));
+    }
+    if let Some(path) = &req.body_file {
+        parts.push(format!("  {}", shell_quote(&format!("@{}", path))));
+    }
+    parts.join(" \\\n")
+}
+
+fn python(req: &Prepared) -> String {
+    let mut out = String::from("import requests\n");
+    if matches!(req.auth, AuthType::Digest { .. }) {
+        out.push_str("from requests.auth import HTTPDigestAuth\n");
+    }
+    out.push('\n');
+    out.push_str(&format!("url = {}\n", string_literal(&req.url)));
+
+    if !req.headers.is_empty() {
+        out.push_str("headers = {\n");
+        for (key, value) in &req.headers {
+            out.push_str(&format!("    {}: {},\n", string_literal(key), string_literal(value)));
+        }
+        out.push_str("}\n");
+    }
+    if let Some(body) = &req.body {
+        out.push_str(&format!("data = {}\n", string_literal(body)));
+    }
+    if let Some(path) = &req.body_file {
+        out.push_str(&format!("data = open({}, \"rb\")\n", string_literal(path)));
+    }
+    let (files, fields): (Vec<_>, Vec<_>) = req.multipart.iter().partition(|(_, v)| v.starts_with('@'));
+    if !fields.is_empty() {
+        out.push_str("data = {\n");
+        for (key, value) in &fields {
+            out.push_str(&format!("    {}: {},\n", string_literal(key), string_literal(value)));
+        }
+        out.push_str("}\n");
+    }
+    if !files.is_empty() {
+        out.push_str("files = {\n");
+        for (key, value) in &files {
+            out.push_str(&format!("    {}: open({}, \"rb\"),\n", string_literal(key), string_literal(&value[1..])));
+        }
+        out.push_str("}\n");
+    }
+
+    out.push_str(&format!("\nresponse = requests.request({}, url", string_literal(req.method)));
+    if !req.headers.is_empty() {
+        out.push_str(", headers=headers");
+    }
+    if req.body.is_some() || req.body_file.is_some() || !fields.is_empty() {
+        out.push_str(", data=data");
+    }
+    if !files.is_empty() {
+        out.push_str(", files=files");
+    }
+    if let AuthType::Digest { username, password } = &req.auth {
+        out.push_str(&format!(", auth=HTTPDigestAuth({}, {})", string_literal(username), string_literal(password)));
+    }
+    out.push_str(")\nprint(response.status_code)\nprint(response.text)\n");
+    out
+}
+
+fn javascript(req: &Prepared) -> String {
+    let mut out = String::new();
+    if req.body_file.is_some() || req.multipart.iter().any(|(_, v)| v.starts_with('@')) {
+        out.push_str("import { openAsBlob } from \"node:fs\";\n\n");
+    }
+    if !req.multipart.is_empty() {
+        out.push_str("const form = new FormData();\n");
+        for (key, value) in &req.multipart {
+            let value = match value.strip_prefix('@') {
+                Some(path) => format!("await openAsBlob({}), {}", string_literal(path), string_literal(file_name(path))),
+                None => string_literal(value),
+            };
+            out.push_str(&format!("form.append({}, {});\n", string_literal(key), value));
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "const response = await fetch({}, {{\n  method: {},\n",
+        string_literal(&req.url),
+        string_literal(req.method)
+    ));
+
+    if !req.headers.is_empty() {
+        out.push_str("  headers: {\n");
+        for (key, value) in &req.headers {
+            out.push_str(&format!("    {}: {},\n", string_literal(key), string_literal(value)));
+        }
+        out.push_str("  },\n");
+    }
+    if let Some(body) = &req.body {
+        out.push_str(&format!("  body: {},\n", string_literal(body)));
+    }
+    if !req.multipart.is_empty() {
+        out.push_str("  body: form,\n");
+    }
+    if let Some(path) = &req.body_file {
+        out.push_str(&format!("  body: await openAsBlob({}),\n", string_literal(path)));
+    }
+
+    out.push_str("});\n\nconsole.log(response.status);\nconsole.log(await response.text());\n");
+    out
+}
+
+fn rust(req: &Prepared) -> String {
+    let mut out = String::new();
+    if !req.multipart.is_empty() {
+        out.push_str("let form = reqwest::multipart::Form::new()");
+        for (key, value) in &req.multipart {
+            match value.strip_prefix('@') {
+                Some(path) => out.push_str(&format!("\n    .file({:?}, {:?})\n    .await?", key, path)),
+                None => out.push_str(&format!("\n    .text({:?}, {:?})", key, value)),
+            }
+        }
+        out.push_str(";\n");
+    }
+    out.push_str("let client = reqwest::Client::new();\nlet response = client\n");
+    out.push_str(&format!(
+        "    .request(reqwest::Method::{}, {:?})\n",
+        req.method, req.url
+    ));
+    for (key, value) in &req.headers {
+        out.push_str(&format!("    .header({:?}, {:?})\n", key, value));
+    }
+    if let Some(body) = &req.body {
+        out.push_str(&format!("    .body({:?})\n", body));
+    }
+    if !req.multipart.is_empty() {
+        out.push_str("    .multipart(form)\n");
+    }
+    if let Some(path) = &req.body_file {
+        out.push_str(&format!("    .body(tokio::fs::File::open({:?}).await?)\n", path));
+    }
+    out.push_str("    .send()\n    .await?;\n\n");
+    out.push_str("println!(\"{}\", response.status());\nprintln!(\"{}\", response.text().await?);\n");
+    out
+}
+
+/// Open the socket, send the body as the first message and print what comes back.
+fn websocket(language: Language, req: &Prepared) -> String {
+    match language {
+        Language::Curl | Language::Httpie => {
+            let mut out = format!("# {} can't hold a WebSocket open; websocat can\n", language.name());
+            if let Some(body) = &req.body {
+                out.push_str(&format!("printf '%s\\n' {} | ", shell_quote(body)));
+            }
+            let mut parts = vec!["websocat".to_string()];
+            for (key, value) in &req.headers {
+                parts.push(format!("  -H {}", shell_quote(&format!("{}: {}", key, value))));
+            }
+            parts.push(format!("  {}", shell_quote(&req.url)));
+            out.push_str(&parts.join(" \\\n"));
+            out
+        }
+        Language::Python => {
+            let mut out = String::from("import asyncio\n\nimport websockets\n\n\nasync def main():\n");
+            out.push_str("    headers = {\n");
+            for (key, value) in &req.headers {
+                out.push_str(&format!("        {}: {},\n", string_literal(key), string_literal(value)));
+            }
+            out.push_str("    }\n");
+            out.push_str(&format!(
+                "    async with websockets.connect({}, additional_headers=headers) as ws:\n",
+                string_literal(&req.url)
+            ));
+            if let Some(body) = &req.body {
+                out.push_str(&format!("        await ws.send({})\n", string_literal(body)));
+            }
+            out.push_str("        async for message in ws:\n            print(message)\n\n\nasyncio.run(main())\n");
+            out
+        }
+        Language::JavaScript => {
+            let mut out = String::from("import WebSocket from \"ws\";\n\n");
+            out.push_str(&format!("const ws = new WebSocket({}, {{\n  headers: {{\n", string_literal(&req.url)));
+            for (key, value) in &req.headers {
+                out.push_str(&format!("    {}: {},\n", string_literal(key), string_literal(value)));
+            }
+            out.push_str("  },\n});\n\n");
+            if let Some(body) = &req.body {
+                out.push_str(&format!("ws.on(\"open\", () => ws.send({}));\n", string_literal(body)));
+            }
+            out.push_str("ws.on(\"message\", (data) => console.log(data.toString()));\n");
+            out
+        }
+        Language::Rust => {
+            let mut out = String::from("use futures_util::{SinkExt, StreamExt};\n");
+            out.push_str("use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};\n\n");
+            out.push_str(&format!("let mut request = {:?}.into_client_request()?;\n", req.url));
+            for (key, value) in &req.headers {
+                out.push_str(&format!("request.headers_mut().insert({:?}, {:?}.parse()?);\n", key, value));
+            }
+            out.push_str("let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;\n");
+            if let Some(body) = &req.body {
+                out.push_str(&format!("socket.send(Message::text({:?})).await?;\n", body));
+            }
+            out.push_str("while let Some(message) = socket.next().await {\n    println!(\"{}\", message?);\n}\n");
+            out
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Quote for POSIX shells: wrap in single quotes, closing and escaping any inside.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// A double-quoted literal valid in both Python and JavaScript.
+fn string_literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_else(|_| format!("\"{}\"", text))
+}
@@ -0,0 +1,446 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+use crate::models::{
+    AuthType, BodyType, HttpMethod, KeyValue, Request, Setting, Settings, SettingsOverride,
+};
+
+/// Options that take a value we don't use; skipped along with their argument.
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-o", "--output", "--connect-timeout", "-w", "--write-out", "--cert", "--key",
+    "-c", "--cookie-jar", "-r", "--range", "--retry", "--resolve",
+];
+
+/// Short options that take a value; the others are flags, which may be
+/// grouped, e.g. `-sL`.
+const SHORT_WITH_VALUE: &str = "XHdTFuAebmxowcr";
+
+/// Parse a `curl` command line, as copied from browser devtools, into a request.
+pub fn parse(command: &str) -> Result<Request, String> {
+    let args = split_args(command)?;
+    let mut args = args.into_iter();
+
+    match args.next() {
+        Some(first) if first == "curl" => {}
+        _ => return Err("not a curl command".to_string()),
+    }
+
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut headers = vec![];
+    let mut data: Vec<String> = vec![];
+    let mut form = vec![];
+    let mut body_file: Option<String> = None;
+    let mut upload = false;
+    let mut user: Option<String> = None;
+    let mut get = false;
+    let mut head = false;
+    let mut follow = false;
+    let mut settings: Vec<(Setting, String)> = vec![];
+
+    // The rest of a group of short options, e.g. `-L` after `-s` in `-sL`
+    let mut grouped: Option<String> = None;
+    while let Some(arg) = grouped.take().or_else(|| args.next()) {
+        // Short options may carry their value attached, e.g. `-XPOST`
+        let short = arg.strip_prefix('-').and_then(|rest| Some((rest.chars().next()?, rest)));
+        let (flag, attached) = match short {
+            Some((c, rest)) if c != '-' && rest.len() > c.len_utf8() => {
+                let tail = &rest[c.len_utf8()..];
+                if SHORT_WITH_VALUE.contains(c) {
+                    (format!("-{}", c), Some(tail.to_string()))
+                } else {
+                    grouped = Some(format!("-{}", tail));
+                    (format!("-{}", c), None)
+                }
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            attached
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+
+        match flag.as_str() {
+            "-X" | "--request" => method = Some(value(&flag)?.to_uppercase()),
+            "-H" | "--header" => {
+                let header = value(&flag)?;
+                if let Some((key, val)) = header.split_once(':') {
+                    headers.push(key_value(key.trim(), val.trim()));
+                }
+            }
+            "-d" | "--data" | "--data-binary" | "--data-ascii" => {
+                let chunk = value(&flag)?;
+                match chunk.strip_prefix('@') {
+                    Some(path) if path != "-" => body_file = Some(path.to_string()),
+                    _ => data.push(chunk),
+                }
+            }
+            "--data-raw" => data.push(value(&flag)?),
+            "-T" | "--upload-file" => {
+                body_file = Some(value(&flag)?);
+                upload = true;
+            }
+            "--data-urlencode" => data.push(encode_data(&value(&flag)?)),
+            "-F" | "--form" => {
+                let field = value(&flag)?;
+                let (key, val) = field.split_once('=').ok_or_else(|| format!("bad form field '{}'", field))?;
+                form.push(key_value(key, val));
+            }
+            "-u" | "--user" => user = Some(value(&flag)?),
+            "--url" => set_url(&mut url, value(&flag)?)?,
+            "-A" | "--user-agent" => headers.push(key_value("User-Agent", &value(&flag)?)),
+            "-e" | "--referer" => headers.push(key_value("Referer", &value(&flag)?)),
+            "-b" | "--cookie" => headers.push(key_value("Cookie", &value(&flag)?)),
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => head = true,
+            "-k" | "--insecure" => settings.push((Setting::VerifyTls, "off".to_string())),
+            "-L" | "--location" => follow = true,
+            "-m" | "--max-time" => settings.push((Setting::Timeout, value(&flag)?)),
+            "--max-redirs" => settings.push((Setting::MaxRedirects, value(&flag)?)),
+            "-x" | "--proxy" => settings.push((Setting::Proxy, value(&flag)?)),
+            "--noproxy" => settings.push((Setting::NoProxy, value(&flag)?)),
+            "--cacert" => settings.push((Setting::CaBundle, value(&flag)?)),
+            f if IGNORED_WITH_VALUE.contains(&f) => {
+                value(f)?;
+            }
+            f if f.starts_with('-') && f.len() > 1 => {} // Boolean flags like --compressed
+            _ => set_url(&mut url, arg)?,
+        }
+    }
+
+    let url = url.ok_or("no URL in curl command")?;
+    let (base, mut params) = split_query(&url);
+
+    let mut body = String::new();
+    if get {
+        for chunk in &data {
+            params.extend(split_query(&format!("?{}", chunk)).1);
+        }
+    } else {
+        body = data.join("&");
+    }
+
+    let method = match method {
+        Some(name) => HttpMethod::from_name(&name)
+            .ok_or_else(|| format!("unsupported method '{}'", name))?,
+        None if head => HttpMethod::Head,
+        None if upload => HttpMethod::Put,
+        None if !body.is_empty() || !form.is_empty() || body_file.is_some() => HttpMethod::Post,
+        None => HttpMethod::Get,
+    };
+
+    let auth = match user {
+        Some(user) => {
+            let (username, password) = user.split_once(':').unwrap_or((&user, ""));
+            AuthType::Basic { username: username.to_string(), password: password.to_string() }
+        }
+        None => basic_auth_from_headers(&mut headers),
+    };
+
+    // curl only follows redirects when told to
+    settings.push((Setting::FollowRedirects, if follow { "on" } else { "off" }.to_string()));
+    let mut overrides = SettingsOverride::default();
+    for (setting, value) in settings {
+        overrides
+            .set(&Settings::default(), setting, &value)
+            .map_err(|e| format!("{}: {}", setting.label(), e))?;
+    }
+
+    let body_type = if !form.is_empty() {
+        BodyType::Multipart
+    } else if body_file.is_some() {
+        BodyType::File
+    } else {
+        BodyType::Auto
+    };
+
+    Ok(Request {
+        params,
+        headers,
+        body,
+        body_type,
+        form,
+        body_file: body_file.unwrap_or_default(),
+        auth,
+        settings: overrides,
+        ..Request::new(method, base)
+    })
+}
+
+/// Take `arg` as the URL; a command gives only one, positional or `--url`.
+fn set_url(url: &mut Option<String>, arg: String) -> Result<(), String> {
+    if url.is_some() {
+        return Err(format!("unexpected argument '{}'", arg));
+    }
+    *url = Some(arg);
+    Ok(())
+}
+
+fn key_value(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        enabled: true,
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// `--data-urlencode` forms: `content`, `=content`, `name=content`.
+fn encode_data(value: &str) -> String {
+    match value.split_once('=') {
+        Some(("", content)) => urlencoding::encode(content).into_owned(),
+        Some((name, content)) => format!("{}={}", name, urlencoding::encode(content)),
+        None => urlencoding::encode(value).into_owned(),
+    }
+}
+
+/// Split `url` into its base and decoded query parameters, dropping any fragment.
+fn split_query(url: &str) -> (String, Vec<KeyValue>) {
+    let url = url.split('#').next().unwrap_or(url);
+    let Some((base, query)) = url.split_once('?') else {
+        return (url.to_string(), vec![]);
+    };
+
+    let decode = |s: &str| {
+        let s = s.replace('+', " ");
+        urlencoding::decode(&s).map(|d| d.into_owned()).unwrap_or(s)
+    };
+
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            key_value(&decode(key), &decode(value))
+        })
+        .collect();
+
+    (base.to_string(), params)
+}
+
+/// Lift an `Authorization: Basic ...` header into `AuthType::Basic`.
+fn basic_auth_from_headers(headers: &mut Vec<KeyValue>) -> AuthType {
+    let Some(idx) = headers.iter().position(|h| h.key.eq_ignore_ascii_case("authorization")) else {
+        return AuthType::None;
+    };
+    let Some(encoded) = headers[idx].value.strip_prefix("Basic ") else {
+        return AuthType::None;
+    };
+    let Some(decoded) = STANDARD.decode(encoded.trim()).ok().and_then(|b| String::from_utf8(b).ok()) else {
+        return AuthType::None;
+    };
+
+    headers.remove(idx);
+    let (username, password) = decoded.split_once(':').unwrap_or((&decoded, ""));
+    AuthType::Basic { username: username.to_string(), password: password.to_string() }
+}
+
+/// Split a command line into arguments the way a POSIX shell would, handling
+/// single, double and `$'...'` quoting, backslash escapes and line continuations.
+fn split_args(command: &str) -> Result<Vec<String>, String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(next) => {
+                    current.push(next);
+                    in_arg = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated ' quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("unterminated \" quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated \" quote".to_string()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some('r') => current.push('\r'),
+                            Some(c) => current.push(c),
+                            None => return Err("unterminated $' quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated $' quote".to_string()),
+                    }
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(request: &Request) -> Vec<(&str, &str)> {
+        request.headers.iter().map(|h| (h.key.as_str(), h.value.as_str())).collect()
+    }
+
+    fn params(request: &Request) -> Vec<(&str, &str)> {
+        request.params.iter().map(|p| (p.key.as_str(), p.value.as_str())).collect()
+    }
+
+    fn basic(username: &str, password: &str) -> AuthType {
+        AuthType::Basic { username: username.to_string(), password: password.to_string() }
+    }
+
+    #[test]
+    fn quoting() {
+        let request = parse(r#"curl 'https://example.com/a b' -H "X-Quote: \"hi\" \$HOME" -H X-Plain:\ spaced"#).unwrap();
+        assert_eq!(request.url, "https://example.com/a b");
+        assert_eq!(headers(&request), [("X-Quote", r#""hi" $HOME"#), ("X-Plain", "spaced")]);
+
+        let request = parse(r"curl https://example.com --data-raw $'one\ntwo\t\'three\''").unwrap();
+        assert_eq!(request.body, "one\ntwo\t'three'");
+
+        assert_eq!(parse("curl 'https://example.com").unwrap_err(), "unterminated ' quote");
+    }
+
+    #[test]
+    fn redirects_followed_only_with_location() {
+        let request = parse("curl https://example.com").unwrap();
+        assert_eq!(request.settings.get(Setting::FollowRedirects), Some("off"));
+        let request = parse("curl --location https://example.com").unwrap();
+        assert_eq!(request.settings.get(Setting::FollowRedirects), Some("on"));
+    }
+
+    #[test]
+    fn one_url() {
+        assert_eq!(parse("curl --url https://example.com").unwrap().url, "https://example.com");
+        let unexpected = Err("unexpected argument 'https://b.example'".to_string());
+        assert_eq!(parse("curl https://a.example https://b.example").map(|r| r.url), unexpected);
+        assert_eq!(parse("curl https://a.example --url https://b.example").map(|r| r.url), unexpected);
+        assert_eq!(parse("curl --url https://a.example https://b.example").map(|r| r.url), unexpected);
+    }
+
+    #[test]
+    fn line_continuations() {
+        let request = parse("curl https://example.com \\\n  -H 'Accept: */*' \\\r\n  -d x=1").unwrap();
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(headers(&request), [("Accept", "*/*")]);
+        assert_eq!(request.body, "x=1");
+        assert_eq!(request.method, HttpMethod::Post);
+    }
+
+    #[test]
+    fn user_is_basic_auth() {
+        let request = parse("curl -u ada:s3cr:et https://example.com").unwrap();
+        assert_eq!(request.auth, basic("ada", "s3cr:et"));
+        let request = parse("curl --user ada https://example.com").unwrap();
+        assert_eq!(request.auth, basic("ada", ""));
+    }
+
+    #[test]
+    fn basic_header_is_lifted_into_auth() {
+        let request = parse("curl https://example.com -H 'Authorization: Basic YWRhOnB3' -H 'Accept: */*'").unwrap();
+        assert_eq!(request.auth, basic("ada", "pw"));
+        assert_eq!(headers(&request), [("Accept", "*/*")]);
+
+        let request = parse("curl https://example.com -H 'Authorization: Bearer abc'").unwrap();
+        assert_eq!(request.auth, AuthType::None);
+        assert_eq!(headers(&request), [("Authorization", "Bearer abc")]);
+    }
+
+    #[test]
+    fn get_moves_data_into_params() {
+        let request = parse("curl -G 'https://example.com/search?q=1' -d a=1 -d 'b=x%20y'").unwrap();
+        assert_eq!(request.method, HttpMethod::Get);
+        assert_eq!(request.url, "https://example.com/search");
+        assert_eq!(params(&request), [("q", "1"), ("a", "1"), ("b", "x y")]);
+        assert_eq!(request.body, "");
+    }
+
+    #[test]
+    fn data_urlencode_forms() {
+        let command = "curl https://example.com --data-urlencode 'msg=a b&c' --data-urlencode '=x y' \
+            --data-urlencode plain";
+        let request = parse(command).unwrap();
+        assert_eq!(request.body, "msg=a%20b%26c&x%20y&plain");
+        assert_eq!(request.method, HttpMethod::Post);
+    }
+
+    #[test]
+    fn grouped_short_flags() {
+        let request = parse("curl -sL https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(request.settings.get(Setting::FollowRedirects), Some("on"));
+
+        let request = parse("curl -kX POST https://example.com -d a=1").unwrap();
+        assert_eq!(request.method, HttpMethod::Post);
+        assert_eq!(request.body, "a=1");
+        assert_eq!(request.settings.get(Setting::VerifyTls), Some("off"));
+    }
+
+    #[test]
+    fn attached_values() {
+        let request = parse("curl -XPUT -kHAccept:text/plain https://example.com").unwrap();
+        assert_eq!(request.method, HttpMethod::Put);
+        assert_eq!(headers(&request), [("Accept", "text/plain")]);
+        assert_eq!(request.settings.get(Setting::VerifyTls), Some("off"));
+    }
+
+    #[test]
+    fn non_ascii_short_flag() {
+        let request = parse("curl -é https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+        let request = parse("curl -sé https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+        let request = parse("curl -Hé:ü https://example.com").unwrap();
+        assert_eq!(headers(&request), [("é", "ü")]);
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::http::{self, Client, HttpResult, RequestData};
+use crate::models::{BodyType, HttpMethod};
+
+/// Just enough of the schema for field completion.
+const INTROSPECTION_QUERY: &str = "query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      name
+      fields(includeDeprecated: true) { name type { ...TypeRef } }
+    }
+  }
+}
+
+fragment TypeRef on __Type {
+  kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } }
+}";
+
+/// The `{"query": ..., "variables": ...}` envelope a GraphQL body is sent in.
+pub fn envelope(query: &str, variables: &str) -> Result<String, String> {
+    let mut body = serde_json::json!({ "query": query });
+    if !variables.trim().is_empty() {
+        body["variables"] = serde_json::from_str(variables).map_err(|e| format!("GraphQL variables: {}", e))?;
+    }
+    Ok(body.to_string())
+}
+
+/// Object and interface types with their fields, and the root operation types.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    query: Option<String>,
+    mutation: Option<String>,
+    subscription: Option<String>,
+    types: HashMap<String, Vec<Field>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    /// As written in SDL, e.g. `[User!]!`.
+    pub type_name: String,
+    /// The named type inside any lists and non-nulls.
+    target: String,
+}
+
+impl Schema {
+    /// Parse the response to the introspection query.
+    pub fn from_introspection(body: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(body).map_err(|e| format!("Schema response is not JSON: {}", e))?;
+        let schema = &json["data"]["__schema"];
+        if !schema.is_object() {
+            let message = json["errors"][0]["message"].as_str().unwrap_or("no schema in response");
+            return Err(format!("Introspection failed: {}", message));
+        }
+
+        let root = |key: &str| schema[key]["name"].as_str().map(String::from);
+        let types = schema["types"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|ty| {
+                let fields = ty["fields"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|field| {
+                        let (type_name, target) = type_ref(&field["type"])?;
+                        Some(Field { name: field["name"].as_str()?.to_string(), type_name, target })
+                    })
+                    .collect();
+                Some((ty["name"].as_str()?.to_string(), fields))
+            })
+            .collect();
+
+        Ok(Self {
+            query: root("queryType"),
+            mutation: root("mutationType"),
+            subscription: root("subscriptionType"),
+            types,
+        })
+    }
+
+    pub fn type_count(&self) -> usize {
+        self.types.len()
+    }
+
+    fn field(&self, type_name: &str, field: &str) -> Option<&Field> {
+        self.types.get(type_name)?.iter().find(|f| f.name == field)
+    }
+
+    /// Fields that could be typed at the end of `before_cursor`, and the
+    /// partial name they would complete.
+    pub fn complete<'s>(&'s self, before_cursor: &str) -> (String, Vec<&'s Field>) {
+        let prefix: String = before_cursor
+            .chars()
+            .rev()
+            .take_while(|&c| c.is_ascii_alphanumeric() || c == '_')
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let scope = self.scope(&before_cursor[..before_cursor.len() - prefix.len()]);
+        let fields = scope
+            .and_then(|ty| self.types.get(&ty))
+            .map(|fields| fields.iter().filter(|f| f.name.starts_with(&prefix)).collect())
+            .unwrap_or_default();
+        (prefix, fields)
+    }
+
+    /// The type of the selection set open at the end of `text`, if known.
+    fn scope(&self, text: &str) -> Option<String> {
+        let mut stack: Vec<Option<String>> = vec![];
+        let mut operation = self.query.clone();
+        let mut last_field: Option<String> = None;
+        let mut type_condition: Option<String> = None;
+        let mut parens = 0;
+        let mut prev = String::new();
+
+        for token in tokens(text) {
+            match token.as_str() {
+                "(" => parens += 1,
+                ")" => parens -= 1,
+                // Braces inside arguments are input objects
+                _ if parens > 0 => {}
+                "{" => {
+                    let scope = match (stack.last(), type_condition.take()) {
+                        (_, Some(ty)) => Some(ty),
+                        (None, None) => operation.clone(),
+                        (Some(parent), None) => parent
+                            .as_deref()
+                            .zip(last_field.as_deref())
+                            .and_then(|(parent, field)| self.field(parent, field))
+                            .map(|field| field.target.clone()),
+                    };
+                    stack.push(scope);
+                    last_field = None;
+                }
+                "}" => {
+                    stack.pop();
+                }
+                "query" if stack.is_empty() => operation = self.query.clone(),
+                "mutation" if stack.is_empty() => operation = self.mutation.clone(),
+                "subscription" if stack.is_empty() => operation = self.subscription.clone(),
+                name if is_name(name) => match prev.as_str() {
+                    "on" => type_condition = Some(name.to_string()),
+                    // Directives and variables
+                    "@" | "$" => {}
+                    // After an alias, this is the field
+                    _ => last_field = Some(name.to_string()),
+                },
+                _ => {}
+            }
+            prev = token;
+        }
+        stack.pop().flatten()
+    }
+}
+
+/// SDL spelling and named type of an introspected type reference.
+fn type_ref(ty: &Value) -> Option<(String, String)> {
+    match ty["kind"].as_str()? {
+        "NON_NULL" => type_ref(&ty["ofType"]).map(|(name, target)| (format!("{}!", name), target)),
+        "LIST" => type_ref(&ty["ofType"]).map(|(name, target)| (format!("[{}]", name), target)),
+        _ => {
+            let name = ty["name"].as_str()?.to_string();
+            Some((name.clone(), name))
+        }
+    }
+}
+
+fn is_name(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+}
+
+/// Names and punctuation, skipping strings, comments and numbers.
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(c) = chars.next_if(|&c| c.is_ascii_alphanumeric() || c == '_') {
+                    name.push(c);
+                }
+                tokens.push(name);
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                while chars.next_if(|&c| c.is_ascii_alphanumeric() || c == '.').is_some() {}
+            }
+            '{' | '}' | '(' | ')' | ':' | '@' | '$' => tokens.push(c.to_string()),
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Fetch the schema of the GraphQL endpoint `data` is sent to.
+pub async fn introspect(client: Client, mut data: RequestData) -> Result<Schema, String> {
+    data.method = HttpMethod::Post;
+    data.body_type = BodyType::GraphQl;
+    data.body = INTROSPECTION_QUERY.to_string();
+    data.variables = String::new();
+
+    match http::fetch(&client, data).await {
+        HttpResult::Success(response) if (200..300).contains(&response.status) => {
+            Schema::from_introspection(&response.body)
+        }
+        HttpResult::Success(response) => {
+            Err(format!("Introspection failed: {} {}", response.status, response.status_text))
+        }
+        HttpResult::Error(e) => Err(e),
+        _ => Err("Introspection failed: unexpected streaming response".to_string()),
+    }
+}
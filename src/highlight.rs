@@ -0,0 +1,352 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::models::{SseEvent, StreamEvent, WsFrame, format_duration};
+use crate::ui::theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Json,
+    Markup,
+    Yaml,
+    Plain,
+}
+
+impl Syntax {
+    /// Pick a highlighter from a `Content-Type` value, falling back to sniffing the body.
+    pub fn detect(content_type: Option<&str>, body: &str) -> Self {
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|m| m.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if mime.ends_with("json") || mime.ends_with("+json") {
+            return Syntax::Json;
+        }
+        if mime.ends_with("xml") || mime.ends_with("html") {
+            return Syntax::Markup;
+        }
+        if mime.ends_with("yaml") || mime.ends_with("yml") {
+            return Syntax::Yaml;
+        }
+
+        let trimmed = body.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            Syntax::Json
+        } else if trimmed.starts_with('<') {
+            Syntax::Markup
+        } else {
+            Syntax::Plain
+        }
+    }
+}
+
+pub fn highlight(text: &str, syntax: Syntax) -> Vec<Line<'static>> {
+    match syntax {
+        Syntax::Json => text.lines().map(json_line).collect(),
+        Syntax::Markup => markup_lines(text),
+        Syntax::Yaml => text.lines().map(yaml_line).collect(),
+        Syntax::Plain => text
+            .lines()
+            .map(|l| Line::from(span(l, theme::TEXT)))
+            .collect(),
+    }
+}
+
+/// The text of a highlighted line without its styling.
+pub fn plain_text(line: &Line) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+/// Overlay `style` on byte ranges of `line`, e.g. search hits. Ranges must be
+/// sorted and non-overlapping.
+pub fn mark_ranges(line: &Line<'static>, ranges: &[(usize, usize, Style)]) -> Line<'static> {
+    let mut spans = vec![];
+    let mut offset = 0;
+
+    for span in &line.spans {
+        let text = span.content.as_ref();
+        let end = offset + text.len();
+        let mut pos = offset;
+
+        for &(start, stop, style) in ranges {
+            let (start, stop) = (start.max(pos), stop.min(end));
+            if start >= stop {
+                continue;
+            }
+            if start > pos {
+                spans.push(Span::styled(text[pos - offset..start - offset].to_string(), span.style));
+            }
+            spans.push(Span::styled(text[start - offset..stop - offset].to_string(), span.style.patch(style)));
+            pos = stop;
+        }
+        if pos < end {
+            spans.push(Span::styled(text[pos - offset..].to_string(), span.style));
+        }
+        offset = end;
+    }
+
+    Line::from(spans)
+}
+
+/// A log entry: when it happened and what it was, then its data indented below.
+pub fn event_lines(event: &StreamEvent) -> Vec<Line<'static>> {
+    match event {
+        StreamEvent::Sse(event) => sse_lines(event),
+        StreamEvent::Ws(frame) => frame_lines(frame),
+    }
+}
+
+fn sse_lines(event: &SseEvent) -> Vec<Line<'static>> {
+    let mut header = vec![
+        span(format!("+{:<8}", format_duration(event.at)), theme::TEXT_DIM),
+        Span::styled(event.event.clone(), Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD)),
+    ];
+    if let Some(id) = &event.id {
+        header.push(span(format!("  id {}", id), theme::TEXT_DIM));
+    }
+    if let Some(retry) = event.retry {
+        header.push(span(format!("  retry {}ms", retry), theme::TEXT_DIM));
+    }
+
+    with_data(Line::from(header), &event.data)
+}
+
+/// A WebSocket frame, marked `→` when sent and `←` when received.
+fn frame_lines(frame: &WsFrame) -> Vec<Line<'static>> {
+    let (arrow, color) = if frame.sent { ("→", theme::METHOD_PUT) } else { ("←", theme::METHOD_GET) };
+    let header = Line::from(vec![
+        span(format!("+{:<8}", format_duration(frame.at)), theme::TEXT_DIM),
+        Span::styled(format!("{} ", arrow), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::styled(frame.kind.label(), Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD)),
+    ]);
+    with_data(header, &frame.data)
+}
+
+fn with_data(header: Line<'static>, data: &str) -> Vec<Line<'static>> {
+    let mut lines = vec![header];
+    lines.extend(highlight(data, Syntax::detect(None, data)).into_iter().map(|mut line| {
+        line.spans.insert(0, Span::raw("  "));
+        line
+    }));
+    lines.push(Line::default());
+    lines
+}
+
+/// The marker closing an event log.
+pub fn stream_end_line(reason: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        format!("── {} ──", reason),
+        Style::default().fg(theme::TEXT_DIM).add_modifier(Modifier::ITALIC),
+    ))
+}
+
+fn span(text: impl Into<String>, color: Color) -> Span<'static> {
+    Span::styled(text.into(), Style::default().fg(color))
+}
+
+/// Color of a bare JSON/YAML scalar.
+pub fn scalar_color(word: &str) -> Color {
+    match word {
+        "true" | "false" => theme::SYNTAX_BOOL,
+        "null" | "~" => theme::SYNTAX_NULL,
+        w if w.parse::<f64>().is_ok() => theme::SYNTAX_NUMBER,
+        _ => theme::TEXT,
+    }
+}
+
+/// Byte length of the quoted string starting at byte `start`, including its quotes.
+fn quoted_len(line: &str, start: usize, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in line[start + 1..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + 2;
+        }
+    }
+    line.len() - start
+}
+
+fn json_line(line: &str) -> Line<'static> {
+    let mut spans = vec![];
+    let mut i = 0;
+
+    while i < line.len() {
+        let c = line[i..].chars().next().unwrap_or(' ');
+        match c {
+            '"' => {
+                let len = quoted_len(line, i, '"');
+                let is_key = line[i + len..].trim_start().starts_with(':');
+                let color = if is_key { theme::SYNTAX_KEY } else { theme::SYNTAX_STRING };
+                spans.push(span(&line[i..i + len], color));
+                i += len;
+            }
+            '{' | '}' | '[' | ']' | ',' | ':' => {
+                spans.push(span(c.to_string(), theme::SYNTAX_PUNCT));
+                i += 1;
+            }
+            c if c.is_whitespace() => {
+                let len = line[i..].find(|c: char| !c.is_whitespace()).unwrap_or(line.len() - i);
+                spans.push(Span::raw(line[i..i + len].to_string()));
+                i += len;
+            }
+            _ => {
+                let len = line[i..]
+                    .find(|c: char| c.is_whitespace() || ",:]}".contains(c))
+                    .unwrap_or(line.len() - i)
+                    .max(c.len_utf8());
+                let word = &line[i..i + len];
+                spans.push(span(word, scalar_color(word)));
+                i += len;
+            }
+        }
+    }
+
+    Line::from(spans)
+}
+
+fn yaml_line(line: &str) -> Line<'static> {
+    let indent_len = line.len() - line.trim_start().len();
+    let mut spans = vec![Span::raw(line[..indent_len].to_string())];
+    let mut rest = &line[indent_len..];
+
+    if rest.starts_with('#') {
+        spans.push(span(rest, theme::TEXT_DIM));
+        return Line::from(spans);
+    }
+    if rest == "---" || rest == "..." {
+        spans.push(span(rest, theme::SYNTAX_PUNCT));
+        return Line::from(spans);
+    }
+    while let Some(item) = rest.strip_prefix("- ") {
+        spans.push(span("- ", theme::SYNTAX_PUNCT));
+        rest = item;
+    }
+
+    // `key: value` or `key:` at end of line
+    let key_end = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let len = quoted_len(rest, 0, quote);
+            rest[len..].starts_with(':').then_some(len)
+        }
+        _ => rest.find(": ").or_else(|| rest.ends_with(':').then(|| rest.len() - 1)),
+    };
+    if let Some(end) = key_end {
+        spans.push(span(&rest[..end], theme::SYNTAX_KEY));
+        spans.push(span(":", theme::SYNTAX_PUNCT));
+        rest = &rest[end + 1..];
+    }
+
+    let value = rest.trim_start();
+    if rest.len() > value.len() {
+        spans.push(Span::raw(rest[..rest.len() - value.len()].to_string()));
+    }
+    let (value, comment) = match value.find(" #") {
+        Some(pos) => (&value[..pos], &value[pos..]),
+        None => (value, ""),
+    };
+    if !value.is_empty() {
+        let color = if value.starts_with('"') || value.starts_with('\'') {
+            theme::SYNTAX_STRING
+        } else {
+            match scalar_color(value) {
+                theme::TEXT => theme::SYNTAX_STRING,
+                color => color,
+            }
+        };
+        spans.push(span(value, color));
+    }
+    if !comment.is_empty() {
+        spans.push(span(comment, theme::TEXT_DIM));
+    }
+
+    Line::from(spans)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MarkupState {
+    Text,
+    TagName,
+    Tag,
+    Comment,
+}
+
+/// XML/HTML: tags, attribute names and values, comments. Tags and comments
+/// may span lines, so state carries over between them.
+fn markup_lines(text: &str) -> Vec<Line<'static>> {
+    let mut state = MarkupState::Text;
+    let mut lines = vec![];
+
+    for line in text.lines() {
+        let mut spans = vec![];
+        let mut i = 0;
+
+        while i < line.len() {
+            let rest = &line[i..];
+            let (len, color) = match state {
+                MarkupState::Comment => {
+                    let len = rest.find("-->").map(|p| p + 3).unwrap_or(rest.len());
+                    if rest[..len].ends_with("-->") {
+                        state = MarkupState::Text;
+                    }
+                    (len, theme::TEXT_DIM)
+                }
+                MarkupState::Text if rest.starts_with("<!--") => {
+                    state = MarkupState::Comment;
+                    (4, theme::TEXT_DIM)
+                }
+                MarkupState::Text if rest.starts_with('<') => {
+                    state = MarkupState::TagName;
+                    let len = if rest.starts_with("</") || rest.starts_with("<?") || rest.starts_with("<!") { 2 } else { 1 };
+                    (len, theme::SYNTAX_PUNCT)
+                }
+                MarkupState::Text => (rest.find('<').unwrap_or(rest.len()), theme::TEXT),
+                MarkupState::TagName => {
+                    let len = rest
+                        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                        .unwrap_or(rest.len());
+                    state = MarkupState::Tag;
+                    (len, theme::SYNTAX_TAG)
+                }
+                MarkupState::Tag => {
+                    let c = rest.chars().next().unwrap_or(' ');
+                    if rest.starts_with("/>") || rest.starts_with("?>") {
+                        state = MarkupState::Text;
+                        (2, theme::SYNTAX_PUNCT)
+                    } else if c == '>' {
+                        state = MarkupState::Text;
+                        (1, theme::SYNTAX_PUNCT)
+                    } else if c == '"' || c == '\'' {
+                        (quoted_len(line, i, c), theme::SYNTAX_STRING)
+                    } else if c == '=' {
+                        (1, theme::SYNTAX_PUNCT)
+                    } else if c.is_whitespace() {
+                        (c.len_utf8(), theme::TEXT)
+                    } else {
+                        let len = rest
+                            .find(|c: char| c.is_whitespace() || "=>/\"'".contains(c))
+                            .unwrap_or(rest.len())
+                            .max(c.len_utf8());
+                        (len, theme::SYNTAX_ATTR)
+                    }
+                }
+            };
+
+            if len == 0 {
+                // A tag name can be empty (e.g. `< `); move on to the tag body
+                continue;
+            }
+            spans.push(span(&rest[..len], color));
+            i += len;
+        }
+
+        lines.push(Line::from(spans));
+    }
+
+    lines
+}
@@ -1,14 +1,52 @@
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use reqwest::Client;
+use reqwest::header::{LOCATION, SET_COOKIE};
+use reqwest::multipart::{Form, Part};
+use tokio::io::AsyncWriteExt;
+use reqwest::{Client, StatusCode};
 use tokio::sync::mpsc;
 
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 
-use crate::models::{AuthType, HttpMethod, KeyValue, Response};
+use super::digest::{DigestSession, DigestSessions};
+use super::oauth::{self, OAuthToken};
+use super::sigv4;
+use crate::files;
+use crate::graphql;
+use crate::models::{
+    AuthType, BodyType, ClientSettings, CookieJar, EventLog, HttpMethod, KeyLocation, KeyValue, OAuthConfig,
+    Response, SpillFile, SseParser, StreamEvent, decode_body,
+};
+
+/// Bodies larger than this are written to a temporary file, keeping only
+/// their start in memory for display.
+const MAX_BODY_IN_MEMORY: usize = 32 * 1024 * 1024;
+
+/// How often download progress is reported.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Headers not sent on to a redirect target on another origin.
+const SENSITIVE_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "cookie2"];
+
+/// Headers describing a body, dropped with it when a redirect turns into a GET.
+const BODY_HEADERS: [&str; 4] = ["content-type", "content-length", "content-encoding", "transfer-encoding"];
 
 #[derive(Debug)]
 pub enum HttpResult {
+    /// Body bytes received so far; the send is still running.
+    Progress { received: u64, total: Option<u64> },
+    /// The headers of an event stream arrived; events follow until `Closed`.
+    Stream(Response),
+    Event(StreamEvent),
+    /// The event stream ended, after `received` bytes; `error` if it broke.
+    Closed { received: u64, error: Option<String> },
+    /// What the send is waiting for, before the request goes out.
+    Status(String),
+    /// A new access token for `config`, to be cached for later sends.
+    Token { config: OAuthConfig, token: OAuthToken },
+    /// Cookies set by a redirect on the way, by the URL that set them.
+    Cookies { url: String, set_cookies: Vec<String> },
     Success(Response),
     Error(String),
 }
@@ -19,73 +57,71 @@ pub struct RequestData {
     pub params: Vec<KeyValue>,
     pub headers: Vec<KeyValue>,
     pub body: String,
+    pub body_type: BodyType,
+    /// Fields of a form or multipart body.
+    pub form: Vec<KeyValue>,
+    /// Path of a file body, streamed when sent.
+    pub body_file: String,
+    /// JSON variables for a GraphQL query.
+    pub variables: String,
     pub auth: AuthType,
+    /// The cached token for an OAuth2 auth that is due for renewal.
+    pub token: Option<OAuthToken>,
+    /// The Digest challenges answered so far, for a Digest auth.
+    pub digest: DigestSessions,
+    /// Resolved client settings; picks the client the request is sent with.
+    pub settings: ClientSettings,
+    /// The jar to send cookies from; `None` sends none.
+    pub cookies: Option<CookieJar>,
 }
 
-pub async fn send_request(client: Client, data: RequestData, tx: mpsc::UnboundedSender<HttpResult>) {
-    let result = execute_request(&client, data).await;
-    let _ = tx.send(result);
-}
-
-async fn execute_request(client: &Client, data: RequestData) -> HttpResult {
-    let url = build_url_with_params(&data.url, &data.params);
-
-    let start = Instant::now();
-
-    let mut request = match data.method {
-        HttpMethod::Get => client.get(&url),
-        HttpMethod::Post => client.post(&url),
-        HttpMethod::Put => client.put(&url),
-        HttpMethod::Patch => client.patch(&url),
-        HttpMethod::Delete => client.delete(&url),
-        HttpMethod::Head => client.head(&url),
-        HttpMethod::Options => client.request(reqwest::Method::OPTIONS, &url),
+/// Execute `data` and report the result with `tag`, so the receiver can
+/// route it back to the send it belongs to.
+pub async fn send_request<T: Clone>(
+    client: Client,
+    data: RequestData,
+    tag: T,
+    tx: mpsc::UnboundedSender<(T, HttpResult)>,
+) {
+    let report = |update| {
+        let _ = tx.send((tag.clone(), update));
     };
+    let result = execute_request(&client, data, report).await;
+    let _ = tx.send((tag, result));
+}
 
-    // Apply authentication header before custom headers
-    request = apply_auth_header(request, &data.auth);
+/// Execute `data` and return the result, without progress reports.
+pub async fn fetch(client: &Client, data: RequestData) -> HttpResult {
+    execute_request(client, data, |_| {}).await
+}
 
-    for header in &data.headers {
-        if header.enabled && !header.key.is_empty() {
-            request = request.header(&header.key, &header.value);
-        }
+/// Send `data`, passing progress and stream events to `report` as they
+/// happen, and return how it ended.
+async fn execute_request(
+    client: &Client,
+    mut data: RequestData,
+    report: impl Fn(HttpResult),
+) -> HttpResult {
+    if let Err(e) = authorize_oauth(client, &mut data, &report).await {
+        return HttpResult::Error(e);
     }
 
-    if !data.body.is_empty() {
-        let has_content_type = data.headers.iter().any(|h| {
-            h.enabled && h.key.to_lowercase() == "content-type"
-        });
-
-        if !has_content_type {
-            // Try to detect if it's JSON
-            if data.body.trim().starts_with('{') || data.body.trim().starts_with('[') {
-                request = request.header("Content-Type", "application/json");
-            } else {
-                request = request.header("Content-Type", "text/plain");
-            }
-        }
+    let start = Instant::now();
+    // Applied here rather than on the client, so event streams can stay
+    // open once their headers arrive
+    let deadline = (data.settings.timeout_secs > 0)
+        .then(|| start + Duration::from_secs(data.settings.timeout_secs));
 
-        request = request.body(data.body);
-    }
-
-    let response = match request.send().await {
-        Ok(r) => r,
-        Err(e) => {
-            let error_msg = if e.is_timeout() {
-                "Request timed out".to_string()
-            } else if e.is_connect() {
-                format!("Connection failed: {}", e)
-            } else if e.is_request() {
-                format!("Invalid request: {}", e)
-            } else {
-                format!("Request failed: {}", e)
-            };
-            return HttpResult::Error(error_msg);
-        }
+    let mut response = match send_following(client, data, deadline, &report).await {
+        Ok(response) => response,
+        Err(e) => return HttpResult::Error(e),
     };
 
-    let elapsed = start.elapsed();
+    let time_to_headers = start.elapsed();
     let status = response.status().as_u16();
+    let version = format!("{:?}", response.version());
+    let final_url = response.url().to_string();
+    let remote_addr = response.remote_addr().map(|addr| addr.to_string());
     let status_text = response
         .status()
         .canonical_reason()
@@ -103,24 +139,307 @@ async fn execute_request(client: &Client, data: RequestData) -> HttpResult {
         })
         .collect();
 
-    let body = match response.text().await {
-        Ok(text) => text,
-        Err(e) => return HttpResult::Error(format!("Failed to read response body: {}", e)),
-    };
-
-    let size_bytes = body.len();
-
-    HttpResult::Success(Response {
+    let mut resp = Response {
         status,
         status_text,
+        url: final_url,
+        version,
+        remote_addr,
         headers,
-        body,
-        elapsed,
-        size_bytes,
-    })
+        body: String::new(),
+        raw_body: None,
+        spill: None,
+        events: None,
+        time_to_headers,
+        elapsed: Duration::ZERO,
+        size_bytes: 0,
+    };
+    if resp.content_type().is_some_and(|ct| ct.starts_with("text/event-stream")) {
+        resp.events = Some(EventLog::default());
+        report(HttpResult::Stream(resp));
+        return read_events(response, start, report).await;
+    }
+
+    let total = response.content_length();
+    let mut buffer = BodyBuffer::default();
+    let mut last_report = Instant::now();
+    loop {
+        match before(deadline, response.chunk()).await {
+            None => return HttpResult::Error("Request timed out".to_string()),
+            Some(Ok(Some(chunk))) => {
+                if let Err(e) = buffer.push(&chunk).await {
+                    return HttpResult::Error(format!("Failed to write response body to disk: {}", e));
+                }
+                if last_report.elapsed() >= PROGRESS_INTERVAL {
+                    report(HttpResult::Progress { received: buffer.received, total });
+                    last_report = Instant::now();
+                }
+            }
+            Some(Ok(None)) => break,
+            Some(Err(e)) => return HttpResult::Error(format!("Failed to read response body: {}", e)),
+        }
+    }
+
+    resp.elapsed = start.elapsed();
+    resp.size_bytes = buffer.received as usize;
+    let (bytes, spill) = match buffer.finish().await {
+        Ok(finished) => finished,
+        Err(e) => return HttpResult::Error(format!("Failed to write response body to disk: {}", e)),
+    };
+    (resp.body, resp.raw_body) = decode_body(bytes);
+    resp.spill = spill;
+    HttpResult::Success(resp)
+}
+
+/// Send `data`, following redirects as its settings allow. Each hop carries
+/// the jar's cookies for its URL and adds those it sets; leaving the origin
+/// drops the auth and any `Authorization` or `Cookie` header.
+async fn send_following(
+    client: &Client,
+    mut data: RequestData,
+    deadline: Option<Instant>,
+    report: &impl Fn(HttpResult),
+) -> Result<reqwest::Response, String> {
+    let mut url = request_url(&data);
+    let mut redirects = 0;
+    loop {
+        let response = send_answering(client, &data, &url, deadline).await?;
+        let status = response.status();
+        let location = response.headers().get(LOCATION).and_then(|location| location.to_str().ok());
+        let Some(location) = location.filter(|_| status.is_redirection() && data.settings.follow_redirects) else {
+            return Ok(response);
+        };
+        if redirects == data.settings.max_redirects {
+            return Err(format!("Too many redirects (more than {})", data.settings.max_redirects));
+        }
+        redirects += 1;
+
+        let from = response.url();
+        let set_cookies: Vec<String> = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(str::to_string)
+            .collect();
+        if !set_cookies.is_empty() {
+            if let Some(jar) = &mut data.cookies
+                && let Some(host) = from.host_str()
+            {
+                jar.store(host, from.path(), set_cookies.iter().map(String::as_str));
+            }
+            report(HttpResult::Cookies { url: from.to_string(), set_cookies });
+        }
+
+        let next = from.join(location).map_err(|e| format!("Invalid redirect to {}: {}", location, e))?;
+        if next.origin() != from.origin() {
+            data.auth = AuthType::None;
+            data.headers.retain(|h| !SENSITIVE_HEADERS.iter().any(|name| h.key.eq_ignore_ascii_case(name)));
+        }
+        // Sent on as a GET without the body, as browsers do
+        if matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER) {
+            if data.method != HttpMethod::Head {
+                data.method = HttpMethod::Get;
+            }
+            data.body.clear();
+            data.form.clear();
+            data.body_file.clear();
+            data.headers.retain(|h| !BODY_HEADERS.iter().any(|name| h.key.eq_ignore_ascii_case(name)));
+        }
+        url = next.to_string();
+    }
+}
+
+/// Send `data` to `url`. A Digest auth answers the challenge from an earlier
+/// request up front, and a new or stale nonce by sending again, once.
+async fn send_answering(
+    client: &Client,
+    data: &RequestData,
+    url: &str,
+    deadline: Option<Instant>,
+) -> Result<reqwest::Response, String> {
+    let AuthType::Digest { username, password } = &data.auth else {
+        return send(client, build_request(client, data, url).await?, deadline).await;
+    };
+    let mut request = build_request(client, data, url).await?;
+    data.digest.authorize(&mut request, username, password)?;
+    let response = send(client, request, deadline).await?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(response);
+    }
+    let Some(challenge) = DigestSession::from_challenge(response.headers()) else { return Ok(response) };
+    let mut request = build_request(client, data, url).await?;
+    data.digest.answer(challenge, &mut request, username, password)?;
+    send(client, request, deadline).await
+}
+
+/// Get an OAuth2 auth a token and send it as a bearer token. An OAuth2 auth
+/// arrives here only when it has no fresh token.
+pub(super) async fn authorize_oauth(
+    client: &Client,
+    data: &mut RequestData,
+    report: &impl Fn(HttpResult),
+) -> Result<(), String> {
+    let AuthType::OAuth2(config) = data.auth.clone() else { return Ok(()) };
+    let token = oauth::authorize(client, &config, data.token.take(), report).await?;
+    data.auth = AuthType::Bearer { token: token.access_token.clone() };
+    report(HttpResult::Token { config, token });
+    Ok(())
+}
+
+/// Report each event of a `text/event-stream` body until the server closes it.
+async fn read_events(mut response: reqwest::Response, start: Instant, report: impl Fn(HttpResult)) -> HttpResult {
+    let mut parser = SseParser::default();
+    let mut received = 0;
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                received += chunk.len() as u64;
+                report(HttpResult::Progress { received, total: None });
+                for mut event in parser.feed(&chunk) {
+                    event.at = start.elapsed();
+                    report(HttpResult::Event(StreamEvent::Sse(event)));
+                }
+            }
+            Ok(None) => return HttpResult::Closed { received, error: None },
+            Err(e) => return HttpResult::Closed { received, error: Some(e.to_string()) },
+        }
+    }
 }
 
-fn build_url_with_params(base_url: &str, params: &[KeyValue]) -> String {
+/// Run `future` unless `deadline` passes first.
+pub(super) async fn before<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// A response body being received: held in memory up to
+/// `MAX_BODY_IN_MEMORY`, then streamed to a temporary file.
+#[derive(Default)]
+struct BodyBuffer {
+    memory: Vec<u8>,
+    spill: Option<(SpillFile, tokio::fs::File)>,
+    received: u64,
+}
+
+impl BodyBuffer {
+    async fn push(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        self.received += chunk.len() as u64;
+        if self.spill.is_none() && self.memory.len() + chunk.len() > MAX_BODY_IN_MEMORY {
+            // An unguessable name, created fresh and private: the temp dir is shared
+            let mut suffix = [0u8; 8];
+            getrandom::fill(&mut suffix).map_err(std::io::Error::other)?;
+            let name = format!("courier-{}-{:016x}.body", std::process::id(), u64::from_ne_bytes(suffix));
+            let spill = SpillFile { path: std::env::temp_dir().join(name) };
+            let mut options = tokio::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            let mut file = options.open(&spill.path).await?;
+            file.write_all(&self.memory).await?;
+            self.spill = Some((spill, file));
+        }
+
+        match &mut self.spill {
+            Some((_, file)) => {
+                let room = MAX_BODY_IN_MEMORY.saturating_sub(self.memory.len()).min(chunk.len());
+                self.memory.extend_from_slice(&chunk[..room]);
+                file.write_all(chunk).await
+            }
+            None => {
+                self.memory.extend_from_slice(chunk);
+                Ok(())
+            }
+        }
+    }
+
+    /// The in-memory bytes, and the file with the whole body if it spilled.
+    async fn finish(self) -> std::io::Result<(Vec<u8>, Option<Arc<SpillFile>>)> {
+        let Some((spill, mut file)) = self.spill else { return Ok((self.memory, None)) };
+        file.flush().await?;
+
+        // Don't let the cut turn a text body into "binary" mid-character
+        let mut memory = self.memory;
+        if let Err(e) = std::str::from_utf8(&memory)
+            && e.error_len().is_none()
+        {
+            memory.truncate(e.valid_up_to());
+        }
+        Ok((memory, Some(Arc::new(spill))))
+    }
+}
+
+/// The request for `data`, sent to `url`, with its auth, headers and body.
+async fn build_request(client: &Client, data: &RequestData, url: &str) -> Result<reqwest::Request, String> {
+    let mut request = match data.method {
+        HttpMethod::Get => client.get(url),
+        HttpMethod::Post => client.post(url),
+        HttpMethod::Put => client.put(url),
+        HttpMethod::Patch => client.patch(url),
+        HttpMethod::Delete => client.delete(url),
+        HttpMethod::Head => client.head(url),
+        HttpMethod::Options => client.request(reqwest::Method::OPTIONS, url),
+        HttpMethod::Ws => return Err("WebSocket requests are opened with open_socket".to_string()),
+    };
+
+    for (name, value) in headers_for(data, url) {
+        request = request.header(name, value);
+    }
+
+    if let Some(content_type) = default_content_type(data) {
+        request = request.header("Content-Type", content_type);
+    }
+    if data.body_type == BodyType::Multipart {
+        if let Some(form) = multipart_form(&data.form).await? {
+            request = request.multipart(form);
+        }
+    } else if data.body_type == BodyType::File && !data.body_file.is_empty() {
+        let (file, len) = open_file(&data.body_file).await?;
+        // A known length avoids chunked encoding, which some servers refuse
+        if let Some(len) = len {
+            request = request.header("Content-Length", len);
+        }
+        request = request.body(file);
+    } else if data.body_type == BodyType::GraphQl && !data.body.trim().is_empty() {
+        request = request.body(graphql::envelope(&data.body, &data.variables)?);
+    } else if let Some(body) = text_body(data) {
+        request = request.body(body);
+    }
+
+    let mut request = request.build().map_err(|e| format!("Invalid request: {}", e))?;
+    // Signed last, over the request exactly as it will be sent
+    if let AuthType::AwsSigV4(credentials) = &data.auth {
+        // Only S3 takes an unsigned payload; for anything else, hash the file
+        let streams_file = data.body_type == BodyType::File && !data.body_file.is_empty();
+        let file_hash = if streams_file && credentials.service != "s3" {
+            Some(sigv4::file_hash(&data.body_file).await?)
+        } else {
+            None
+        };
+        sigv4::sign(&mut request, credentials, file_hash.as_deref(), SystemTime::now())?;
+    }
+    Ok(request)
+}
+
+async fn send(client: &Client, request: reqwest::Request, deadline: Option<Instant>) -> Result<reqwest::Response, String> {
+    match before(deadline, client.execute(request)).await {
+        None => Err("Request timed out".to_string()),
+        Some(Ok(response)) => Ok(response),
+        Some(Err(e)) => Err(if e.is_timeout() {
+            "Request timed out".to_string()
+        } else if e.is_connect() {
+            format!("Connection failed: {}", e)
+        } else if e.is_request() {
+            format!("Invalid request: {}", e)
+        } else {
+            format!("Request failed: {}", e)
+        }),
+    }
+}
+
+pub fn build_url_with_params(base_url: &str, params: &[KeyValue]) -> String {
     let enabled_params: Vec<_> = params
         .iter()
         .filter(|p| p.enabled && !p.key.is_empty())
@@ -143,21 +462,166 @@ fn build_url_with_params(base_url: &str, params: &[KeyValue]) -> String {
     }
 }
 
-fn apply_auth_header(
-    request: reqwest::RequestBuilder,
-    auth: &AuthType,
-) -> reqwest::RequestBuilder {
+/// The URL `data` is sent to, with its params and an API key sent in the
+/// query string.
+pub fn request_url(data: &RequestData) -> String {
+    match &data.auth {
+        AuthType::ApiKey { key, value, location: KeyLocation::Query } if !key.is_empty() => {
+            let api_key = KeyValue { key: key.clone(), value: value.clone(), ..KeyValue::default() };
+            let params: Vec<KeyValue> = data.params.iter().cloned().chain([api_key]).collect();
+            build_url_with_params(&data.url, &params)
+        }
+        _ => build_url_with_params(&data.url, &data.params),
+    }
+}
+
+/// The headers sent with `data`: the auth header first, then the request's
+/// own. An API key sent as a cookie, and the jar's cookies for the URL, join
+/// any `Cookie` header.
+pub fn request_headers(data: &RequestData) -> Vec<(String, String)> {
+    headers_for(data, &request_url(data))
+}
+
+/// The headers sent with `data` to `url`, which a redirect may have changed.
+fn headers_for(data: &RequestData, url: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = auth_header(&data.auth).into_iter().collect();
+    headers.extend(
+        data.headers
+            .iter()
+            .filter(|h| h.enabled && !h.key.is_empty())
+            .map(|h| (h.key.clone(), h.value.clone())),
+    );
+
+    if let AuthType::ApiKey { key, value, location: KeyLocation::Cookie } = &data.auth
+        && !key.is_empty()
+    {
+        add_cookie(&mut headers, format!("{}={}", key, value));
+    }
+    if let Some(jar) = &data.cookies
+        && let Ok(url) = reqwest::Url::parse(url)
+        && let Some(host) = url.host_str()
+        && let Some(cookies) = jar.header_for(host, url.path(), matches!(url.scheme(), "https" | "wss"))
+    {
+        add_cookie(&mut headers, cookies);
+    }
+    headers
+}
+
+/// Append `cookie` to the `Cookie` header, adding one if there is none.
+fn add_cookie(headers: &mut Vec<(String, String)>, cookie: String) {
+    match headers.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case("cookie")) {
+        Some((_, existing)) if existing.trim().is_empty() => *existing = cookie,
+        Some((_, existing)) => *existing = format!("{}; {}", existing, cookie),
+        None => headers.push(("Cookie".to_string(), cookie)),
+    }
+}
+
+/// The header `auth` adds to a request, if any.
+fn auth_header(auth: &AuthType) -> Option<(String, String)> {
     match auth {
-        AuthType::None => request,
+        AuthType::None => None,
         AuthType::Basic { username, password } => {
             let credentials = format!("{}:{}", username, password);
             let encoded = STANDARD.encode(credentials);
-            request.header("Authorization", format!("Basic {}", encoded))
+            Some(("Authorization".to_string(), format!("Basic {}", encoded)))
         }
         AuthType::Bearer { token } if !token.is_empty() => {
-            request.header("Authorization", format!("Bearer {}", token))
+            Some(("Authorization".to_string(), format!("Bearer {}", token)))
         }
-        AuthType::ApiKey { key, value } if !key.is_empty() => request.header(key, value),
-        _ => request,
+        AuthType::ApiKey { key, value, location: KeyLocation::Header } if !key.is_empty() => {
+            Some((key.clone(), value.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Content-Type to add for a non-empty body when the user didn't set one.
+/// Multipart bodies get theirs, with the boundary, from the client.
+pub fn default_content_type(data: &RequestData) -> Option<&'static str> {
+    let has_content_type = data.headers.iter().any(|h| {
+        h.enabled && h.key.to_lowercase() == "content-type"
+    });
+    if has_content_type || data.body_type == BodyType::Multipart {
+        return None;
+    }
+    if data.body_type == BodyType::File {
+        let path = files::expand_home(&data.body_file);
+        return (!data.body_file.is_empty()).then(|| files::mime_type(&path));
+    }
+    let body = text_body(data)?;
+    if let Some(content_type) = data.body_type.content_type() {
+        return Some(content_type);
+    }
+
+    // Try to detect if it's JSON
+    let body = body.trim();
+    if body.starts_with('{') || body.starts_with('[') {
+        Some("application/json")
+    } else {
+        Some("text/plain")
+    }
+}
+
+/// The body as sent, for every type but multipart and file; `None` if empty.
+/// GraphQL queries are wrapped in their JSON envelope.
+pub fn text_body(data: &RequestData) -> Option<String> {
+    let body = match data.body_type {
+        BodyType::Multipart | BodyType::File => return None,
+        // Invalid variables are reported when sending
+        BodyType::GraphQl if data.body.trim().is_empty() => return None,
+        BodyType::GraphQl => return graphql::envelope(&data.body, &data.variables).ok(),
+        BodyType::Form => form_fields(&data.form)
+            .map(|(key, value)| format!("{}={}", urlencoding::encode(key), urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&"),
+        _ => data.body.clone(),
+    };
+    (!body.is_empty()).then_some(body)
+}
+
+/// Enabled fields with a name, as (name, value).
+pub fn form_fields(fields: &[KeyValue]) -> impl Iterator<Item = (&str, &str)> {
+    fields
+        .iter()
+        .filter(|f| f.enabled && !f.key.is_empty())
+        .map(|f| (f.key.as_str(), f.value.as_str()))
+}
+
+/// Open a file to stream as (part of) a body, with its size if known.
+async fn open_file(path: &str) -> Result<(tokio::fs::File, Option<u64>), String> {
+    let file = tokio::fs::File::open(files::expand_home(path))
+        .await
+        .map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let len = file.metadata().await.ok().map(|meta| meta.len());
+    Ok((file, len))
+}
+
+/// Build a multipart body; `@path` values are streamed from disk as file parts.
+async fn multipart_form(fields: &[KeyValue]) -> Result<Option<Form>, String> {
+    let mut form = Form::new();
+    let mut empty = true;
+    for (name, value) in form_fields(fields) {
+        empty = false;
+        let Some(path) = value.strip_prefix('@') else {
+            form = form.text(name.to_string(), value.to_string());
+            continue;
+        };
+
+        let (file, len) = open_file(path).await?;
+        let part = match len {
+            Some(len) => Part::stream_with_length(file, len),
+            None => Part::stream(file),
+        };
+        let path = files::expand_home(path);
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let part = part
+            .file_name(file_name)
+            .mime_str(files::mime_type(&path))
+            .map_err(|e| e.to_string())?;
+        form = form.part(name.to_string(), part);
     }
+    Ok((!empty).then_some(form))
 }
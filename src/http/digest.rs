@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use md5::Md5;
+use reqwest::Url;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue, WWW_AUTHENTICATE};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "MD5" => Some(Algorithm::Md5),
+            "MD5-SESS" => Some(Algorithm::Md5Sess),
+            "SHA-256" => Some(Algorithm::Sha256),
+            "SHA-256-SESS" => Some(Algorithm::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Sess => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn hash(self, data: &str) -> String {
+        let digest = match self {
+            Algorithm::Md5 | Algorithm::Md5Sess => Md5::digest(data).to_vec(),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => Sha256::digest(data).to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// A server's Digest challenge for one realm, answered by each request to
+/// it until the server sends a new one.
+#[derive(Debug, Clone)]
+pub struct DigestSession {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    /// Whether the server offered `qop=auth`; if not, the RFC 2069 response is sent.
+    qop: bool,
+    cnonce: String,
+    /// Requests sent with this nonce so far.
+    count: u32,
+}
+
+impl DigestSession {
+    /// The first Digest challenge we can answer in a 401's `WWW-Authenticate`.
+    pub fn from_challenge(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(Self::parse)
+    }
+
+    fn parse(header: &str) -> Option<Self> {
+        // Other schemes may share the header, e.g. `Basic realm="x", Digest ...`
+        let lower = header.to_ascii_lowercase();
+        let start = lower
+            .match_indices("digest ")
+            .map(|(i, _)| i)
+            .find(|&i| lower[..i].trim_end().is_empty() || lower[..i].trim_end().ends_with(','))?;
+        let params = auth_params(&header[start + "digest ".len()..]);
+        let param = |name: &str| {
+            params.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.clone())
+        };
+
+        let algorithm = match param("algorithm") {
+            Some(name) => Algorithm::parse(&name)?,
+            None => Algorithm::Md5,
+        };
+        // Only `auth` is supported, not `auth-int`
+        let qop = match param("qop") {
+            Some(qop) if qop.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth")) => true,
+            Some(_) => return None,
+            None => false,
+        };
+        let mut cnonce = [0u8; 16];
+        getrandom::fill(&mut cnonce).ok()?;
+
+        Some(Self {
+            realm: param("realm").unwrap_or_default(),
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            algorithm,
+            qop,
+            cnonce: cnonce.iter().map(|b| format!("{:02x}", b)).collect(),
+            count: 0,
+        })
+    }
+
+    /// Add the `Authorization` answering the challenge to `request`, counting
+    /// it against the nonce.
+    fn authorize(&mut self, request: &mut reqwest::Request, username: &str, password: &str) -> Result<(), String> {
+        self.count += 1;
+        let url = request.url();
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let nc = format!("{:08x}", self.count);
+        let hash = |data: String| self.algorithm.hash(&data);
+
+        let mut ha1 = hash(format!("{}:{}:{}", username, self.realm, password));
+        if matches!(self.algorithm, Algorithm::Md5Sess | Algorithm::Sha256Sess) {
+            ha1 = hash(format!("{}:{}:{}", ha1, self.nonce, self.cnonce));
+        }
+        let ha2 = hash(format!("{}:{}", request.method(), uri));
+        let response = if self.qop {
+            hash(format!("{}:{}:{}:{}:auth:{}", ha1, self.nonce, nc, self.cnonce, ha2))
+        } else {
+            hash(format!("{}:{}:{}", ha1, self.nonce, ha2))
+        };
+
+        let mut header = format!(
+            "Digest username={}, realm={}, nonce={}, uri={}, algorithm={}, response=\"{}\"",
+            quoted(username),
+            quoted(&self.realm),
+            quoted(&self.nonce),
+            quoted(&uri),
+            self.algorithm.name(),
+            response
+        );
+        if self.qop {
+            let _ = write!(header, ", qop=auth, nc={}, cnonce=\"{}\"", nc, self.cnonce);
+        }
+        if let Some(opaque) = &self.opaque {
+            let _ = write!(header, ", opaque={}", quoted(opaque));
+        }
+        let value = HeaderValue::from_str(&header).map_err(|_| "Invalid Digest credentials".to_string())?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+/// The Digest challenges answered so far, shared with the sends in flight so
+/// that each use of a nonce is counted once.
+#[derive(Debug, Clone, Default)]
+pub struct DigestSessions {
+    inner: Arc<Mutex<Sessions>>,
+}
+
+#[derive(Debug, Default)]
+struct Sessions {
+    /// By origin, user and realm.
+    sessions: HashMap<(String, String, String), DigestSession>,
+    /// The realm each path was last challenged for, by origin, user and path.
+    realms: HashMap<(String, String, String), String>,
+}
+
+impl DigestSessions {
+    /// Answer the last challenge for the realm `request`'s URL belongs to, if
+    /// known: the one it was challenged for before, or the server's only one.
+    pub fn authorize(&self, request: &mut reqwest::Request, username: &str, password: &str) -> Result<(), String> {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let origin = origin(request.url());
+        let path = (origin.clone(), username.to_string(), request.url().path().to_string());
+        let realm = match inner.realms.get(&path) {
+            Some(realm) => realm.clone(),
+            None => {
+                let mut realms = inner.sessions.keys().filter(|(o, user, _)| *o == origin && user == username);
+                match (realms.next(), realms.next()) {
+                    (Some((_, _, realm)), None) => realm.clone(),
+                    _ => return Ok(()),
+                }
+            }
+        };
+        match inner.sessions.get_mut(&(origin, username.to_string(), realm)) {
+            Some(session) => session.authorize(request, username, password),
+            None => Ok(()),
+        }
+    }
+
+    /// Keep `challenge`, from a 401 to `request`, for its realm and answer it.
+    pub fn answer(
+        &self,
+        mut challenge: DigestSession,
+        request: &mut reqwest::Request,
+        username: &str,
+        password: &str,
+    ) -> Result<(), String> {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let origin = origin(request.url());
+        let path = (origin.clone(), username.to_string(), request.url().path().to_string());
+        challenge.authorize(request, username, password)?;
+        inner.realms.insert(path, challenge.realm.clone());
+        inner.sessions.insert((origin, username.to_string(), challenge.realm.clone()), challenge);
+        Ok(())
+    }
+}
+
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// `key=value` and `key="quoted value"` pairs, up to the next scheme.
+fn auth_params(text: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|&c| c == ',' || c.is_whitespace()).is_some() {}
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|&c| c != '=' && c != ',' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        // A bare token starts the next challenge
+        if key.is_empty() || chars.next_if_eq(&'=').is_none() {
+            return params;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != ',') {
+                value.push(c);
+            }
+        }
+        params.push((key, value.trim_end().to_string()));
+    }
+}
+
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The challenge of the RFC 7616 example, with the client nonce it uses.
+    fn rfc_7616_session(algorithm: &str) -> DigestSession {
+        let header = format!(
+            "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm={}, \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+            algorithm
+        );
+        let mut session = DigestSession::parse(&header).unwrap();
+        session.cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ".to_string();
+        session
+    }
+
+    fn get(url: &str) -> reqwest::Request {
+        reqwest::Client::new().get(url).build().unwrap()
+    }
+
+    fn params(request: &reqwest::Request) -> Vec<(String, String)> {
+        match request.headers().get(AUTHORIZATION) {
+            Some(value) => auth_params(value.to_str().unwrap().strip_prefix("Digest ").unwrap()),
+            None => vec![],
+        }
+    }
+
+    fn param(request: &reqwest::Request, name: &str) -> Option<String> {
+        params(request).into_iter().find(|(key, _)| key == name).map(|(_, value)| value)
+    }
+
+    /// RFC 7616 §3.9.1, with SHA-256 and then MD5.
+    #[test]
+    fn rfc_7616_example() {
+        let mut request = get("http://www.example.org/dir/index.html");
+        rfc_7616_session("SHA-256").authorize(&mut request, "Mufasa", "Circle of Life").unwrap();
+        assert_eq!(
+            request.headers()[AUTHORIZATION],
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", uri=\"/dir/index.html\", algorithm=SHA-256, \
+             response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\", qop=auth, \
+             nc=00000001, cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+
+        let mut request = get("http://www.example.org/dir/index.html");
+        rfc_7616_session("MD5").authorize(&mut request, "Mufasa", "Circle of Life").unwrap();
+        assert_eq!(param(&request, "response").as_deref(), Some("8ca523f5e9506fed4657c9700eebdbec"));
+    }
+
+    #[test]
+    fn one_realm_is_answered_everywhere() {
+        let sessions = DigestSessions::default();
+        let challenge = DigestSession::parse("Digest realm=\"api\", nonce=\"n\"").unwrap();
+        sessions.answer(challenge, &mut get("http://example.org/login"), "user", "pass").unwrap();
+
+        let mut request = get("http://example.org/users");
+        sessions.authorize(&mut request, "user", "pass").unwrap();
+        assert_eq!(param(&request, "realm").as_deref(), Some("api"));
+
+        let mut request = get("http://example.org/users");
+        sessions.authorize(&mut request, "someone else", "pass").unwrap();
+        assert!(params(&request).is_empty());
+    }
+
+    #[test]
+    fn sessions_are_kept_per_realm() {
+        let sessions = DigestSessions::default();
+        let challenge = |realm: &str, nonce: &str| {
+            DigestSession::parse(&format!("Digest realm=\"{}\", nonce=\"{}\", qop=\"auth\"", realm, nonce)).unwrap()
+        };
+
+        let mut request = get("http://example.org/a");
+        sessions.answer(challenge("a", "nonce-a"), &mut request, "user", "pass").unwrap();
+        let mut request = get("http://example.org/b");
+        sessions.answer(challenge("b", "nonce-b"), &mut request, "user", "pass").unwrap();
+
+        // Each path answers its own realm's nonce, counting on from the 401
+        let mut request = get("http://example.org/a?page=2");
+        sessions.authorize(&mut request, "user", "pass").unwrap();
+        assert_eq!(param(&request, "nonce").as_deref(), Some("nonce-a"));
+        assert_eq!(param(&request, "nc").as_deref(), Some("00000002"));
+
+        // Sends in flight share the count
+        let mut request = get("http://example.org/a");
+        sessions.clone().authorize(&mut request, "user", "pass").unwrap();
+        assert_eq!(param(&request, "nc").as_deref(), Some("00000003"));
+
+        let mut request = get("http://example.org/b");
+        sessions.authorize(&mut request, "user", "pass").unwrap();
+        assert_eq!(param(&request, "nonce").as_deref(), Some("nonce-b"));
+        assert_eq!(param(&request, "nc").as_deref(), Some("00000002"));
+
+        // With two realms, an unseen path can't be answered up front
+        let mut request = get("http://example.org/c");
+        sessions.authorize(&mut request, "user", "pass").unwrap();
+        assert!(params(&request).is_empty());
+    }
+}
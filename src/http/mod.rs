@@ -1,15 +1,167 @@
 mod client;
+mod digest;
+mod oauth;
+mod sigv4;
+mod websocket;
 
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
 
-pub use client::{send_request, HttpResult, RequestData};
+use reqwest::redirect::Policy;
+use reqwest::{Certificate, NoProxy, Proxy};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+pub use client::{
+    default_content_type, fetch, form_fields, request_headers, request_url, send_request, text_body, HttpResult,
+    RequestData,
+};
+pub use digest::DigestSessions;
+pub use oauth::OAuthToken;
+pub use websocket::{open_socket, WsCommand};
 pub use reqwest::Client;
 
-const DEFAULT_TIMEOUT_SECS: u64 = 30;
+use crate::files;
+use crate::models::ClientSettings;
+
+/// A client for `settings`. Redirects are followed by the send itself, so
+/// each hop can carry and fill the cookie jar.
+pub fn build_client(settings: &ClientSettings) -> Result<Client, String> {
+    let mut builder = Client::builder()
+        .user_agent(&settings.user_agent)
+        .redirect(Policy::none())
+        .danger_accept_invalid_certs(!settings.verify_tls);
+
+    if !settings.ca_bundle.is_empty() {
+        let pem = read_ca_bundle(&settings.ca_bundle)?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid CA bundle {}: {}", settings.ca_bundle, e))?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if !settings.proxy.is_empty() {
+        let proxy = Proxy::all(&settings.proxy)
+            .map_err(|e| format!("Invalid proxy {}: {}", settings.proxy, e))?
+            .no_proxy(NoProxy::from_string(&settings.no_proxy));
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().map_err(|e| format!("Cannot build HTTP client: {}", e))
+}
+
+/// TLS for connections made without a `Client`, such as WebSockets,
+/// verified the way `build_client` would.
+fn tls_config(settings: &ClientSettings) -> Result<Arc<ClientConfig>, String> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Cannot set up TLS: {}", e))?;
+    if !settings.verify_tls {
+        let verifier = Arc::new(AcceptAnyCert(provider));
+        return Ok(Arc::new(builder.dangerous().with_custom_certificate_verifier(verifier).with_no_client_auth()));
+    }
+
+    let mut roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    if !settings.ca_bundle.is_empty() {
+        let pem = read_ca_bundle(&settings.ca_bundle)?;
+        for cert in CertificateDer::pem_slice_iter(&pem) {
+            let invalid = |e: String| format!("Invalid CA bundle {}: {}", settings.ca_bundle, e);
+            let cert = cert.map_err(|e| invalid(e.to_string()))?;
+            roots.add(cert).map_err(|e| invalid(e.to_string()))?;
+        }
+    }
+    Ok(Arc::new(builder.with_root_certificates(roots).with_no_client_auth()))
+}
+
+/// The PEM file at `path`, which may start with `~`.
+fn read_ca_bundle(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(files::expand_home(path)).map_err(|e| format!("Cannot read CA bundle {}: {}", path, e))
+}
+
+/// Takes any certificate, for Verify TLS off; handshake signatures are still
+/// checked.
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// The settings `build_client` uses. The timeout and redirects are applied
+/// per send, so requests differing only in those share a client.
+#[derive(PartialEq, Eq, Hash)]
+struct ClientKey {
+    verify_tls: bool,
+    ca_bundle: String,
+    proxy: String,
+    no_proxy: String,
+    user_agent: String,
+}
+
+impl ClientKey {
+    fn new(settings: &ClientSettings) -> Self {
+        Self {
+            verify_tls: settings.verify_tls,
+            ca_bundle: settings.ca_bundle.clone(),
+            proxy: settings.proxy.clone(),
+            no_proxy: settings.no_proxy.clone(),
+            user_agent: settings.user_agent.clone(),
+        }
+    }
+}
+
+/// One client per distinct way of building one, so connections are reused
+/// between requests that are sent the same way.
+#[derive(Default)]
+pub struct ClientCache {
+    clients: HashMap<ClientKey, Client>,
+}
 
-pub fn build_client() -> Result<Client, reqwest::Error> {
-    Client::builder()
-        .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
-        .user_agent("Courier/0.1.0")
-        .build()
+impl ClientCache {
+    pub fn get(&mut self, settings: &ClientSettings) -> Result<Client, String> {
+        let key = ClientKey::new(settings);
+        if let Some(client) = self.clients.get(&key) {
+            return Ok(client.clone());
+        }
+        let client = build_client(settings)?;
+        self.clients.insert(key, client.clone());
+        Ok(client)
+    }
 }
@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use super::Client;
+use super::client::{HttpResult, before};
+use crate::models::{Grant, OAuthConfig};
+use crate::utils;
+
+/// Tokens this close to expiring are renewed before a send.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for the browser to come back to the redirect listener.
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// An access token from a token endpoint.
+#[derive(Debug, Clone)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// `None` when the server didn't say; such tokens are used until forgotten.
+    pub expires_at: Option<SystemTime>,
+}
+
+impl OAuthToken {
+    /// Whether the token can be sent as it is.
+    pub fn is_fresh(&self) -> bool {
+        self.expires_at.is_none_or(|at| SystemTime::now() + EXPIRY_MARGIN < at)
+    }
+
+    /// Time left until it expires, zero once it has.
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_at.map(|at| at.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+}
+
+/// Get a new token for `config`: by refreshing `cached` if it has a refresh
+/// token, else through the configured grant.
+pub async fn authorize(
+    client: &Client,
+    config: &OAuthConfig,
+    cached: Option<OAuthToken>,
+    report: &impl Fn(HttpResult),
+) -> Result<OAuthToken, String> {
+    if let Some(refresh_token) = cached.and_then(|token| token.refresh_token) {
+        report(HttpResult::Status("Refreshing access token...".to_string()));
+        let params = [("grant_type", "refresh_token"), ("refresh_token", &refresh_token), ("scope", &config.scope)];
+        // Refresh tokens expire and get revoked too; fall back to the grant
+        if let Ok(mut token) = request_token(client, config, &params).await {
+            token.refresh_token.get_or_insert(refresh_token);
+            return Ok(token);
+        }
+    }
+
+    report(HttpResult::Status("Fetching access token...".to_string()));
+    match config.grant {
+        Grant::ClientCredentials => {
+            let params = [("grant_type", "client_credentials"), ("scope", &config.scope)];
+            request_token(client, config, &params).await
+        }
+        Grant::Password => {
+            let params = [
+                ("grant_type", "password"),
+                ("username", &config.username),
+                ("password", &config.password),
+                ("scope", &config.scope),
+            ];
+            request_token(client, config, &params).await
+        }
+        Grant::RefreshToken => {
+            let params =
+                [("grant_type", "refresh_token"), ("refresh_token", &config.refresh_token), ("scope", &config.scope)];
+            let mut token = request_token(client, config, &params).await?;
+            token.refresh_token.get_or_insert_with(|| config.refresh_token.clone());
+            Ok(token)
+        }
+        Grant::AuthorizationCode => authorization_code(client, config, report).await,
+    }
+}
+
+/// Sign in through the browser, which is redirected back to a listener on
+/// the loopback interface with the code.
+async fn authorization_code(
+    client: &Client,
+    config: &OAuthConfig,
+    report: &impl Fn(HttpResult),
+) -> Result<OAuthToken, String> {
+    let mut url = reqwest::Url::parse(&config.auth_url).map_err(|e| format!("Invalid auth URL: {}", e))?;
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Cannot listen for the sign-in redirect: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+
+    let verifier = random_string()?;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = random_string()?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256")
+        .append_pair("state", &state);
+    if !config.scope.is_empty() {
+        url.query_pairs_mut().append_pair("scope", &config.scope);
+    }
+
+    let status = match utils::open_in_browser(url.as_str()) {
+        Ok(()) => "Waiting for sign-in in the browser...".to_string(),
+        Err(_) => format!("Open this URL to sign in: {}", url),
+    };
+    report(HttpResult::Status(status));
+
+    let code = before(Some(Instant::now() + SIGN_IN_TIMEOUT), receive_code(&listener, &state))
+        .await
+        .ok_or("Timed out waiting for sign-in")??;
+
+    report(HttpResult::Status("Fetching access token...".to_string()));
+    let params = [
+        ("grant_type", "authorization_code"),
+        ("code", &code),
+        ("redirect_uri", &redirect_uri),
+        ("code_verifier", &verifier),
+    ];
+    request_token(client, config, &params).await
+}
+
+/// Wait for the redirect carrying our `state`, and answer the browser.
+async fn receive_code(listener: &TcpListener, state: &str) -> Result<String, String> {
+    loop {
+        let (mut stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
+        let mut buf = vec![0; 8192];
+        let n = stream.read(&mut buf).await.unwrap_or(0);
+        // "GET /callback?code=...&state=... HTTP/1.1"
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let target = request.split_whitespace().nth(1).unwrap_or("/");
+        let Ok(url) = reqwest::Url::parse(&format!("http://127.0.0.1{}", target)) else { continue };
+        let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+
+        // Anything else, like the browser asking for a favicon
+        if url.path() != "/callback" || param("state").as_deref() != Some(state) {
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            continue;
+        }
+
+        let result = match param("code") {
+            Some(code) => Ok(code),
+            None => {
+                let error = param("error_description").or_else(|| param("error"));
+                Err(format!("Sign-in failed: {}", error.as_deref().unwrap_or("no code in the redirect")))
+            }
+        };
+        let page = match &result {
+            Ok(_) => "Signed in. You can close this tab and return to courier.",
+            Err(_) => "Sign-in failed. You can close this tab and return to courier.",
+        };
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        return result;
+    }
+}
+
+/// POST `params` and the client's credentials to the token endpoint.
+async fn request_token(client: &Client, config: &OAuthConfig, params: &[(&str, &str)]) -> Result<OAuthToken, String> {
+    if config.token_url.is_empty() {
+        return Err("OAuth2 token URL is empty".to_string());
+    }
+    let mut form: Vec<(&str, &str)> = params.iter().copied().filter(|(_, value)| !value.is_empty()).collect();
+    form.push(("client_id", &config.client_id));
+    if !config.client_secret.is_empty() {
+        form.push(("client_secret", &config.client_secret));
+    }
+
+    let response = client
+        .post(&config.token_url)
+        .header("Accept", "application/json")
+        .form(&form)
+        .timeout(TOKEN_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| format!("Token request failed: {}", e))?;
+    let json: Value = serde_json::from_str(&body).unwrap_or_default();
+
+    match json["access_token"].as_str() {
+        Some(access_token) if status.is_success() => {
+            let expires_in = json["expires_in"].as_u64().or_else(|| json["expires_in"].as_str()?.parse().ok());
+            Ok(OAuthToken {
+                access_token: access_token.to_string(),
+                refresh_token: json["refresh_token"].as_str().map(String::from),
+                expires_at: expires_in.map(|secs| SystemTime::now() + Duration::from_secs(secs)),
+            })
+        }
+        _ => {
+            let error = json["error_description"].as_str().or(json["error"].as_str());
+            Err(match error {
+                Some(error) => format!("Token request failed: {}", error),
+                None => format!("Token request failed: {}", status),
+            })
+        }
+    }
+}
+
+/// 32 random bytes, URL-safe; used for the PKCE verifier and the state.
+fn random_string() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("No randomness available: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
@@ -0,0 +1,279 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Response as Handshake;
+use tokio_tungstenite::tungstenite::http::header::USER_AGENT;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::http::Response as HttpResponse;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+use super::{Client, tls_config};
+use super::client::{HttpResult, RequestData, authorize_oauth, before, request_headers, request_url};
+use super::digest::DigestSession;
+use super::sigv4;
+use crate::models::{AuthType, ClientSettings, EventLog, FrameKind, Response, StreamEvent, WsFrame};
+use crate::utils::format_size;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How long to wait for the server to answer our close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sent from the UI to an open socket.
+#[derive(Debug)]
+pub enum WsCommand {
+    Send(String),
+    Ping,
+    Close,
+}
+
+/// Open a WebSocket for `data` and report its frames with `tag` until either
+/// side closes it. `commands` sends messages and closes it; `client` fetches
+/// OAuth2 tokens.
+pub async fn open_socket<T: Clone>(
+    client: Client,
+    data: RequestData,
+    tag: T,
+    tx: mpsc::UnboundedSender<(T, HttpResult)>,
+    commands: mpsc::UnboundedReceiver<WsCommand>,
+) {
+    let report = |update| {
+        let _ = tx.send((tag.clone(), update));
+    };
+    let result = run_socket(&client, data, report, commands).await;
+    let _ = tx.send((tag, result));
+}
+
+async fn run_socket(
+    client: &Client,
+    mut data: RequestData,
+    report: impl Fn(HttpResult),
+    mut commands: mpsc::UnboundedReceiver<WsCommand>,
+) -> HttpResult {
+    if let Err(e) = authorize_oauth(client, &mut data, &report).await {
+        return HttpResult::Error(e);
+    }
+    let url = socket_url(&request_url(&data));
+    let start = Instant::now();
+    let deadline = (data.settings.timeout_secs > 0)
+        .then(|| start + Duration::from_secs(data.settings.timeout_secs));
+    if let Err(e) = check_proxy(&url, &data.settings) {
+        return HttpResult::Error(e);
+    }
+    let connector = match tls_config(&data.settings) {
+        Ok(config) => Connector::Rustls(config),
+        Err(e) => return HttpResult::Error(e),
+    };
+
+    let digest = match &data.auth {
+        AuthType::Digest { username, password } => Some((username, password)),
+        _ => None,
+    };
+    let mut request = match handshake_request(&data, &url) {
+        Ok(request) => request,
+        Err(e) => return HttpResult::Error(e),
+    };
+    // Answer the challenge from an earlier request up front
+    if let Some((username, password)) = digest
+        && let Err(e) = data.digest.authorize(&mut request, username, password)
+    {
+        return HttpResult::Error(e);
+    }
+    let mut connected = connect(&url, &request, connector.clone(), deadline).await;
+
+    // A new or stale nonce is challenged; answer it and connect again, once
+    if let Some((username, password)) = digest
+        && let Err(Refused::Http(response)) = &connected
+        && response.status() == StatusCode::UNAUTHORIZED
+        && let Some(challenge) = DigestSession::from_challenge(response.headers())
+    {
+        let mut request = match handshake_request(&data, &url) {
+            Ok(request) => request,
+            Err(e) => return HttpResult::Error(e),
+        };
+        if let Err(e) = data.digest.answer(challenge, &mut request, username, password) {
+            return HttpResult::Error(e);
+        }
+        connected = connect(&url, &request, connector, deadline).await;
+    }
+    let (socket, handshake) = match connected {
+        Ok(connected) => connected,
+        Err(Refused::Http(response)) => {
+            return HttpResult::Error(format!("Connection failed: {}", Error::Http(response)));
+        }
+        Err(Refused::Other(e)) => return HttpResult::Error(e),
+    };
+
+    let remote_addr = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.peer_addr().ok(),
+        MaybeTlsStream::Rustls(stream) => stream.get_ref().0.peer_addr().ok(),
+        _ => None,
+    };
+    report(HttpResult::Stream(Response {
+        status: handshake.status().as_u16(),
+        status_text: handshake.status().canonical_reason().unwrap_or("Unknown").to_string(),
+        url,
+        version: format!("{:?}", handshake.version()),
+        remote_addr: remote_addr.map(|addr| addr.to_string()),
+        headers: handshake
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or("<binary>").to_string()))
+            .collect(),
+        body: String::new(),
+        raw_body: None,
+        spill: None,
+        events: Some(EventLog::default()),
+        time_to_headers: start.elapsed(),
+        elapsed: Duration::ZERO,
+        size_bytes: 0,
+    }));
+
+    let log = |sent: bool, message: &Message| {
+        if let Some((kind, data)) = describe(message) {
+            let frame = WsFrame { sent, kind, data, at: start.elapsed() };
+            report(HttpResult::Event(StreamEvent::Ws(frame)));
+        }
+    };
+
+    let (mut sink, mut stream) = socket.split();
+    let mut received = 0;
+    let mut close_deadline = None;
+    loop {
+        tokio::select! {
+            message = before(close_deadline, stream.next()) => match message {
+                None => {
+                    let error = Some("the server did not answer the close".to_string());
+                    return HttpResult::Closed { received, error };
+                }
+                Some(None) => return HttpResult::Closed { received, error: None },
+                Some(Some(Err(e))) => return HttpResult::Closed { received, error: Some(e.to_string()) },
+                Some(Some(Ok(message))) => {
+                    received += message.len() as u64;
+                    log(false, &message);
+                    // Answered automatically on the next read
+                    if let Message::Ping(payload) = message {
+                        log(true, &Message::Pong(payload));
+                    }
+                }
+            },
+            command = commands.recv(), if close_deadline.is_none() => {
+                let message = match command {
+                    Some(WsCommand::Send(text)) => Message::text(text),
+                    Some(WsCommand::Ping) => Message::Ping(Default::default()),
+                    // Dropping the sender closes the socket too
+                    Some(WsCommand::Close) | None => {
+                        close_deadline = Some(Instant::now() + CLOSE_TIMEOUT);
+                        Message::Close(Some(CloseFrame { code: CloseCode::Normal, reason: "".into() }))
+                    }
+                };
+                log(true, &message);
+                if let Err(e) = sink.send(message).await {
+                    return HttpResult::Closed { received, error: Some(e.to_string()) };
+                }
+            }
+        }
+    }
+}
+
+/// The opening handshake as a plain GET with the request's headers and user
+/// agent, signed for an AWS auth; the upgrade headers are added when connecting.
+fn handshake_request(data: &RequestData, url: &str) -> Result<reqwest::Request, String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid request: {}", e))?;
+    let mut request = reqwest::Request::new(reqwest::Method::GET, url);
+    for (key, value) in request_headers(data) {
+        let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) else {
+            return Err(format!("Invalid header '{}'", key));
+        };
+        request.headers_mut().append(name, value);
+    }
+    if !data.settings.user_agent.is_empty()
+        && !request.headers().contains_key(USER_AGENT)
+        && let Ok(agent) = HeaderValue::from_str(&data.settings.user_agent)
+    {
+        request.headers_mut().insert(USER_AGENT, agent);
+    }
+    if let AuthType::AwsSigV4(credentials) = &data.auth {
+        sigv4::sign(&mut request, credentials, None, SystemTime::now())?;
+    }
+    Ok(request)
+}
+
+/// Why a connection wasn't opened.
+enum Refused {
+    /// The server answered the handshake with something other than an upgrade.
+    Http(Box<HttpResponse<Option<Vec<u8>>>>),
+    Other(String),
+}
+
+/// Upgrade a connection to `url` with the headers of `request`, over TLS
+/// from `connector` for `wss://`.
+async fn connect(
+    url: &str,
+    request: &reqwest::Request,
+    connector: Connector,
+    deadline: Option<Instant>,
+) -> Result<(Socket, Handshake), Refused> {
+    let mut handshake = url.into_client_request().map_err(|e| Refused::Other(format!("Invalid request: {}", e)))?;
+    for (name, value) in request.headers() {
+        handshake.headers_mut().append(name, value.clone());
+    }
+    let connecting = tokio_tungstenite::connect_async_tls_with_config(handshake, None, false, Some(connector));
+    match before(deadline, connecting).await {
+        None => Err(Refused::Other("Request timed out".to_string())),
+        Some(Ok(connected)) => Ok(connected),
+        Some(Err(Error::Http(response))) => Err(Refused::Http(response)),
+        Some(Err(e)) => Err(Refused::Other(format!("Connection failed: {}", e))),
+    }
+}
+
+/// Refuse a socket that the proxy setting would route through a proxy, which
+/// WebSockets don't support yet.
+fn check_proxy(url: &str, settings: &ClientSettings) -> Result<(), String> {
+    if settings.proxy.is_empty() {
+        return Ok(());
+    }
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.trim_matches(['[', ']']).to_ascii_lowercase()))
+        .unwrap_or_default();
+    let bypassed = settings.no_proxy.split(',').map(|entry| entry.trim().to_ascii_lowercase()).any(|entry| {
+        let domain = entry.trim_start_matches('.');
+        entry == "*" || (!domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain))))
+    });
+    if bypassed {
+        return Ok(());
+    }
+    Err(format!("WebSockets can't go through a proxy yet; add {} to No proxy to connect directly", host))
+}
+
+/// `http(s)://` URLs are accepted for convenience.
+fn socket_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else {
+        url.to_string()
+    }
+}
+
+/// How a message appears in the log; `None` for raw frames, which are never read.
+fn describe(message: &Message) -> Option<(FrameKind, String)> {
+    let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    Some(match message {
+        Message::Text(text) => (FrameKind::Text, text.to_string()),
+        Message::Binary(bytes) => (FrameKind::Binary, format!("<{} of binary data>", format_size(bytes.len() as u64))),
+        Message::Ping(payload) => (FrameKind::Ping, lossy(payload)),
+        Message::Pong(payload) => (FrameKind::Pong, lossy(payload)),
+        Message::Close(Some(frame)) => (FrameKind::Close(Some(frame.code.into())), frame.reason.to_string()),
+        Message::Close(None) => (FrameKind::Close(None), String::new()),
+        Message::Frame(_) => return None,
+    })
+}
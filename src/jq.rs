@@ -0,0 +1,583 @@
+//! A jq-compatible subset for filtering response bodies: paths, `.[]`,
+//! pipes, commas, array construction, comparisons, `and`/`or`, and the
+//! builtins `select`, `map`, `keys`, `length` and `not`.
+
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Field(String),
+    Ident(String),
+    Str(String),
+    Num(f64),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Pipe,
+    Comma,
+    Question,
+    Cmp(CmpOp),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Literal(Value),
+    Array(Option<Box<Expr>>),
+    Compare(CmpOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Call(String, Option<Box<Expr>>),
+}
+
+/// A compiled filter.
+#[derive(Debug, Clone)]
+pub struct Filter(Expr);
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.pipe()?;
+        match parser.peek() {
+            None => Ok(Filter(expr)),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    pub fn run(&self, input: &Value) -> Result<Vec<Value>, String> {
+        eval(&self.0, input)
+    }
+}
+
+/// Run `source` over a JSON `body`, pretty-printing each output on its own.
+pub fn apply(source: &str, body: &str) -> Result<String, String> {
+    let filter = Filter::parse(source)?;
+    let input: Value = serde_json::from_str(body).map_err(|_| "response is not JSON".to_string())?;
+    let outputs = filter.run(&input)?;
+    Ok(outputs
+        .iter()
+        .map(|v| serde_json::to_string_pretty(v).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '.' => {
+                chars.next();
+                let mut name = String::new();
+                while let Some(&c) = chars.peek().filter(|&&c| is_ident(c)) {
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(if name.is_empty() { Token::Dot } else { Token::Field(name) });
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(c) => text.push(c),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(text));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::from(c);
+                chars.next();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+                let value = number.parse().map_err(|_| format!("bad number '{}'", number))?;
+                tokens.push(Token::Num(value));
+            }
+            c if is_ident(c) => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek().filter(|&&c| is_ident(c)) {
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(name));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                let op = match (c, eq) {
+                    ('=', true) => CmpOp::Eq,
+                    ('!', true) => CmpOp::Ne,
+                    ('<', false) => CmpOp::Lt,
+                    ('<', true) => CmpOp::Le,
+                    ('>', false) => CmpOp::Gt,
+                    ('>', true) => CmpOp::Ge,
+                    _ => return Err(format!("unexpected '{}'", c)),
+                };
+                tokens.push(Token::Cmp(op));
+            }
+            _ => {
+                chars.next();
+                tokens.push(match c {
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '|' => Token::Pipe,
+                    ',' => Token::Comma,
+                    '?' => Token::Question,
+                    _ => return Err(format!("unexpected '{}'", c)),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}", token))
+        }
+    }
+
+    fn eat_keyword(&mut self, word: &str) -> bool {
+        self.eat(&Token::Ident(word.to_string()))
+    }
+
+    fn pipe(&mut self) -> Result<Expr, String> {
+        let mut expr = self.comma()?;
+        while self.eat(&Token::Pipe) {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+        Ok(expr)
+    }
+
+    fn comma(&mut self) -> Result<Expr, String> {
+        let mut expr = self.or()?;
+        while self.eat(&Token::Comma) {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.or()?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.compare()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.compare()?));
+        }
+        Ok(expr)
+    }
+
+    fn compare(&mut self) -> Result<Expr, String> {
+        let left = self.postfix()?;
+        if let Some(Token::Cmp(op)) = self.peek().cloned() {
+            self.pos += 1;
+            let right = self.postfix()?;
+            return Ok(Expr::Compare(op, Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Field(_)) => {
+                    let Some(Token::Field(name)) = self.next() else { unreachable!() };
+                    expr = Expr::Field(Box::new(expr), name);
+                }
+                // `.a."b"` and `.a.[0]`
+                Some(Token::Dot) => match self.tokens.get(self.pos + 1).cloned() {
+                    Some(Token::Str(name)) => {
+                        self.pos += 2;
+                        expr = Expr::Field(Box::new(expr), name);
+                    }
+                    Some(Token::LBracket) => self.pos += 1,
+                    _ => return Ok(expr),
+                },
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    expr = if self.eat(&Token::RBracket) {
+                        Expr::Iterate(Box::new(expr))
+                    } else {
+                        let index = self.pipe()?;
+                        self.expect(Token::RBracket)?;
+                        Expr::Index(Box::new(expr), Box::new(index))
+                    };
+                }
+                Some(Token::Question) => {
+                    self.pos += 1;
+                    expr = Expr::Optional(Box::new(expr));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Dot) => match self.peek() {
+                Some(Token::Str(_)) => {
+                    let Some(Token::Str(name)) = self.next() else { unreachable!() };
+                    Ok(Expr::Field(Box::new(Expr::Identity), name))
+                }
+                _ => Ok(Expr::Identity),
+            },
+            Some(Token::Field(name)) => Ok(Expr::Field(Box::new(Expr::Identity), name)),
+            Some(Token::Str(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::Num(n)) => Ok(Expr::Literal(number(n))),
+            Some(Token::LParen) => {
+                let expr = self.pipe()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::LBracket) => {
+                if self.eat(&Token::RBracket) {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.pipe()?;
+                self.expect(Token::RBracket)?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "keys" | "length" | "not" => Ok(Expr::Call(name, None)),
+                "select" | "map" => {
+                    self.expect(Token::LParen)?;
+                    let arg = self.pipe()?;
+                    self.expect(Token::RParen)?;
+                    Ok(Expr::Call(name, Some(Box::new(arg))))
+                }
+                _ => Err(format!("unknown function '{}'", name)),
+            },
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of filter".to_string()),
+        }
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// jq's total order: null < false < true < numbers < strings < arrays < objects.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    let rank = |v: &Value| match v {
+        Value::Null => 0,
+        Value::Bool(false) => 1,
+        Value::Bool(true) => 2,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut xk: Vec<_> = x.keys().collect();
+            let mut yk: Vec<_> = y.keys().collect();
+            xk.sort();
+            yk.sort();
+            xk.cmp(&yk).then_with(|| {
+                xk.iter()
+                    .map(|k| compare(&x[*k], &y[*k]))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Evaluate `f` for every output of `sub`, collecting the results.
+fn each(sub: &Expr, input: &Value, mut f: impl FnMut(Value) -> Result<Vec<Value>, String>) -> Result<Vec<Value>, String> {
+    let mut out = vec![];
+    for value in eval(sub, input)? {
+        out.extend(f(value)?);
+    }
+    Ok(out)
+}
+
+fn index(value: &Value, key: &Value) -> Result<Value, String> {
+    match (value, key) {
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (Value::Object(map), Value::String(k)) => Ok(map.get(k).cloned().unwrap_or(Value::Null)),
+        (Value::Array(items), Value::Number(n)) => {
+            let i = n.as_f64().unwrap_or(0.0).floor() as i64;
+            let i = if i < 0 { items.len() as i64 + i } else { i };
+            Ok(usize::try_from(i).ok().and_then(|i| items.get(i)).cloned().unwrap_or(Value::Null))
+        }
+        _ => Err(format!("Cannot index {} with {}", type_name(value), type_name(key))),
+    }
+}
+
+fn eval(expr: &Expr, input: &Value) -> Result<Vec<Value>, String> {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Field(target, name) => each(target, input, |v| Ok(vec![index(&v, &Value::String(name.clone()))?])),
+        Expr::Index(target, key) => each(target, input, |v| {
+            eval(key, input)?.iter().map(|k| index(&v, k)).collect()
+        }),
+        Expr::Iterate(target) => each(target, input, |v| match v {
+            Value::Array(items) => Ok(items),
+            Value::Object(map) => Ok(map.into_iter().map(|(_, v)| v).collect()),
+            other => Err(format!("Cannot iterate over {}", type_name(&other))),
+        }),
+        Expr::Optional(inner) => Ok(eval(inner, input).unwrap_or_default()),
+        Expr::Pipe(left, right) => each(left, input, |v| eval(right, &v)),
+        Expr::Comma(left, right) => {
+            let mut out = eval(left, input)?;
+            out.extend(eval(right, input)?);
+            Ok(out)
+        }
+        Expr::Array(None) => Ok(vec![Value::Array(vec![])]),
+        Expr::Array(Some(inner)) => Ok(vec![Value::Array(eval(inner, input)?)]),
+        Expr::Compare(op, left, right) => each(left, input, |l| {
+            each(right, input, |r| {
+                let ord = compare(&l, &r);
+                let result = match op {
+                    CmpOp::Eq => ord.is_eq(),
+                    CmpOp::Ne => ord.is_ne(),
+                    CmpOp::Lt => ord.is_lt(),
+                    CmpOp::Le => ord.is_le(),
+                    CmpOp::Gt => ord.is_gt(),
+                    CmpOp::Ge => ord.is_ge(),
+                };
+                Ok(vec![Value::Bool(result)])
+            })
+        }),
+        Expr::And(left, right) => each(left, input, |l| {
+            if !truthy(&l) {
+                return Ok(vec![Value::Bool(false)]);
+            }
+            Ok(eval(right, input)?.iter().map(|r| Value::Bool(truthy(r))).collect())
+        }),
+        Expr::Or(left, right) => each(left, input, |l| {
+            if truthy(&l) {
+                return Ok(vec![Value::Bool(true)]);
+            }
+            Ok(eval(right, input)?.iter().map(|r| Value::Bool(truthy(r))).collect())
+        }),
+        Expr::Call(name, arg) => call(name, arg.as_deref(), input),
+    }
+}
+
+fn call(name: &str, arg: Option<&Expr>, input: &Value) -> Result<Vec<Value>, String> {
+    match (name, arg) {
+        ("select", Some(cond)) => Ok(eval(cond, input)?
+            .iter()
+            .filter(|v| truthy(v))
+            .map(|_| input.clone())
+            .collect()),
+        ("map", Some(f)) => {
+            let each = Expr::Pipe(Box::new(Expr::Iterate(Box::new(Expr::Identity))), Box::new(f.clone()));
+            Ok(vec![Value::Array(eval(&each, input)?)])
+        }
+        ("not", None) => Ok(vec![Value::Bool(!truthy(input))]),
+        ("length", None) => {
+            let len = match input {
+                Value::Null => Value::from(0),
+                Value::Number(n) => number(n.as_f64().unwrap_or(0.0).abs()),
+                Value::String(s) => Value::from(s.chars().count()),
+                Value::Array(items) => Value::from(items.len()),
+                Value::Object(map) => Value::from(map.len()),
+                Value::Bool(_) => return Err("boolean has no length".to_string()),
+            };
+            Ok(vec![len])
+        }
+        ("keys", None) => match input {
+            Value::Object(map) => {
+                let mut keys: Vec<_> = map.keys().cloned().collect();
+                keys.sort();
+                Ok(vec![Value::from(keys)])
+            }
+            Value::Array(items) => Ok(vec![Value::from((0..items.len()).collect::<Vec<_>>())]),
+            other => Err(format!("{} has no keys", type_name(other))),
+        },
+        _ => Err(format!("unknown function '{}'", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDER: &str = r#"{
+        "id": 7,
+        "customer": {"name": "Ada", "tags": ["vip", "early"]},
+        "items": [
+            {"name": "pen", "price": 2, "qty": 10},
+            {"name": "lamp", "price": 40, "qty": 1},
+            {"name": "desk", "price": 250, "qty": 1}
+        ],
+        "a b": true
+    }"#;
+
+    /// The outputs of `filter` over `input`, as compact JSON.
+    fn run(filter: &str, input: &str) -> Result<Vec<String>, String> {
+        let input: Value = serde_json::from_str(input).unwrap();
+        Ok(Filter::parse(filter)?.run(&input)?.iter().map(Value::to_string).collect())
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(run(".", "[1]").unwrap(), ["[1]"]);
+        assert_eq!(run(".customer.name", ORDER).unwrap(), [r#""Ada""#]);
+        assert_eq!(run(".customer.tags[1]", ORDER).unwrap(), [r#""early""#]);
+        assert_eq!(run(".items[-1].name", ORDER).unwrap(), [r#""desk""#]);
+        assert_eq!(run(r#"."a b""#, ORDER).unwrap(), ["true"]);
+        assert_eq!(run(r#".customer["name"]"#, ORDER).unwrap(), [r#""Ada""#]);
+        assert_eq!(run(".customer.tags.[0]", ORDER).unwrap(), [r#""vip""#]);
+    }
+
+    #[test]
+    fn iteration() {
+        assert_eq!(run(".items[].name", ORDER).unwrap(), [r#""pen""#, r#""lamp""#, r#""desk""#]);
+        assert_eq!(run(".customer[]", ORDER).unwrap(), [r#""Ada""#, r#"["vip","early"]"#]);
+        assert_eq!(run("[.items[].qty]", ORDER).unwrap(), ["[10,1,1]"]);
+        assert_eq!(run(".[]", "[]").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn pipes_and_commas() {
+        assert_eq!(run(".items | length", ORDER).unwrap(), ["3"]);
+        assert_eq!(run(".customer | .name, .tags[0]", ORDER).unwrap(), [r#""Ada""#, r#""vip""#]);
+        assert_eq!(run(".items[0] | keys", ORDER).unwrap(), [r#"["name","price","qty"]"#]);
+        assert_eq!(run(".customer.name | length", ORDER).unwrap(), ["3"]);
+    }
+
+    #[test]
+    fn select_with_comparisons() {
+        let names = |filter: &str| run(&format!(".items[] | select({}) | .name", filter), ORDER).unwrap();
+        assert_eq!(names(".price > 10"), [r#""lamp""#, r#""desk""#]);
+        assert_eq!(names(".price >= 40 and .price < 100"), [r#""lamp""#]);
+        assert_eq!(names(r#".name == "pen" or .price == 250"#), [r#""pen""#, r#""desk""#]);
+        assert_eq!(names(".qty != 1"), [r#""pen""#]);
+        assert_eq!(names(".qty <= 1 | not"), [r#""pen""#]);
+        assert_eq!(run("map(select(.qty == 1) | .price)", r#"[{"qty": 1, "price": 3}, {"qty": 2}]"#).unwrap(), ["[3]"]);
+    }
+
+    #[test]
+    fn missing_keys_are_null() {
+        assert_eq!(run(".nope", ORDER).unwrap(), ["null"]);
+        assert_eq!(run(".nope.deeper[0]", ORDER).unwrap(), ["null"]);
+        assert_eq!(run(".items[9].name", ORDER).unwrap(), ["null"]);
+        assert_eq!(run(".nope | length", ORDER).unwrap(), ["0"]);
+        assert_eq!(run(".nope[]?", ORDER).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(run(".items[0", ORDER), Err("expected RBracket".to_string()));
+        assert_eq!(run("select(.a", ORDER), Err("expected RParen".to_string()));
+        assert_eq!(run(r#".name == "pen"#, ORDER), Err("unterminated string".to_string()));
+        assert_eq!(run("sort", ORDER), Err("unknown function 'sort'".to_string()));
+        assert_eq!(run(".id)", ORDER), Err("unexpected RParen".to_string()));
+        assert_eq!(run(".id[]", ORDER), Err("Cannot iterate over number".to_string()));
+        assert_eq!(run(".id.x", ORDER), Err("Cannot index number with string".to_string()));
+        assert_eq!(apply(".", "<html>"), Err("response is not JSON".to_string()));
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+
+use ratatui::widgets::ListState;
+use serde_json::Value;
+
+/// One visible line of the tree.
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub depth: usize,
+    /// JSON path of the node this row belongs to, e.g. `$.data[3].name`.
+    pub path: String,
+    pub key: Option<String>,
+    pub kind: RowKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum RowKind {
+    /// Opening bracket of an object or array, or the whole node when collapsed.
+    Open { bracket: char, len: usize, collapsed: bool },
+    Close(char),
+    /// A scalar, serialized as JSON.
+    Scalar(String),
+}
+
+impl RowKind {
+    /// `{3 keys}` / `[124 items]` for collapsed nodes.
+    pub fn summary(bracket: char, len: usize) -> String {
+        let (noun, close) = if bracket == '{' { ("key", '}') } else { ("item", ']') };
+        let plural = if len == 1 { "" } else { "s" };
+        format!("{}{} {}{}{}", bracket, len, noun, plural, close)
+    }
+}
+
+/// A foldable view of a JSON document.
+pub struct JsonTree {
+    root: Value,
+    collapsed: HashSet<String>,
+    rows: Vec<TreeRow>,
+    pub state: ListState,
+}
+
+impl JsonTree {
+    /// Parse `body` into a tree; `None` unless it is a JSON object or array.
+    pub fn parse(body: &str) -> Option<Self> {
+        let root: Value = serde_json::from_str(body).ok()?;
+        if !root.is_object() && !root.is_array() {
+            return None;
+        }
+
+        let mut tree = Self {
+            root,
+            collapsed: HashSet::new(),
+            rows: vec![],
+            state: ListState::default().with_selected(Some(0)),
+        };
+        tree.rebuild();
+        Some(tree)
+    }
+
+    pub fn rows(&self) -> &[TreeRow] {
+        &self.rows
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.state.selected().unwrap_or(0)
+    }
+
+    /// JSON path of the node under the cursor.
+    pub fn cursor_path(&self) -> &str {
+        self.rows.get(self.cursor()).map(|r| r.path.as_str()).unwrap_or("$")
+    }
+
+    pub fn move_cursor(&mut self, delta: isize) {
+        let max = self.rows.len().saturating_sub(1);
+        let cursor = self.cursor().saturating_add_signed(delta).min(max);
+        self.state.select(Some(cursor));
+    }
+
+    pub fn cursor_first(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    pub fn cursor_last(&mut self) {
+        self.state.select(Some(self.rows.len().saturating_sub(1)));
+    }
+
+    /// Fold or unfold the container under the cursor. `None` toggles.
+    pub fn set_expanded(&mut self, expanded: Option<bool>) {
+        let Some(row) = self.rows.get(self.cursor()) else { return };
+        if matches!(row.kind, RowKind::Scalar(_)) {
+            return;
+        }
+
+        let path = row.path.clone();
+        let expand = expanded.unwrap_or_else(|| self.collapsed.contains(&path));
+        if expand {
+            self.collapsed.remove(&path);
+        } else {
+            self.collapsed.insert(path.clone());
+        }
+        self.rebuild();
+
+        // Folding from a closing bracket moves the cursor onto the node itself
+        if let Some(pos) = self.rows.iter().position(|r| r.path == path) {
+            self.state.select(Some(pos));
+        }
+    }
+
+    pub fn expand_all(&mut self) {
+        self.collapsed.clear();
+        self.rebuild_keeping_cursor();
+    }
+
+    /// Fold every container below the root.
+    pub fn collapse_all(&mut self) {
+        let mut paths = vec![];
+        collect_containers(&self.root, "$".to_string(), &mut paths);
+        self.collapsed = paths.into_iter().filter(|p| p != "$").collect();
+        self.rebuild_keeping_cursor();
+    }
+
+    fn rebuild_keeping_cursor(&mut self) {
+        let path = self.cursor_path().to_string();
+        self.rebuild();
+        // Land on the nearest visible ancestor of the previous cursor
+        let pos = self
+            .rows
+            .iter()
+            .rposition(|r| is_within(&path, &r.path) && !matches!(r.kind, RowKind::Close(_)))
+            .unwrap_or(0);
+        self.state.select(Some(pos));
+    }
+
+    fn rebuild(&mut self) {
+        let mut rows = vec![];
+        push_rows(&self.root, None, "$".to_string(), 0, &self.collapsed, &mut rows);
+        self.rows = rows;
+        let max = self.rows.len().saturating_sub(1);
+        self.state.select(Some(self.cursor().min(max)));
+    }
+}
+
+/// Whether `path` is `ancestor` itself or somewhere beneath it.
+fn is_within(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    let is_ident = key.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if is_ident {
+        format!("{}.{}", parent, key)
+    } else {
+        format!("{}[{}]", parent, serde_json::to_string(key).unwrap_or_default())
+    }
+}
+
+fn children<'a>(value: &'a Value, path: &str) -> Vec<(Option<String>, String, &'a Value)> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (Some(k.clone()), child_path(path, k), v))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (None, format!("{}[{}]", path, i), v))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn push_rows(
+    value: &Value,
+    key: Option<String>,
+    path: String,
+    depth: usize,
+    collapsed: &HashSet<String>,
+    rows: &mut Vec<TreeRow>,
+) {
+    let (bracket, close, len) = match value {
+        Value::Object(map) => ('{', '}', map.len()),
+        Value::Array(items) => ('[', ']', items.len()),
+        scalar => {
+            let text = serde_json::to_string(scalar).unwrap_or_default();
+            rows.push(TreeRow { depth, path, key, kind: RowKind::Scalar(text) });
+            return;
+        }
+    };
+
+    let is_collapsed = collapsed.contains(&path);
+    rows.push(TreeRow {
+        depth,
+        path: path.clone(),
+        key,
+        kind: RowKind::Open { bracket, len, collapsed: is_collapsed },
+    });
+    if is_collapsed {
+        return;
+    }
+
+    for (child_key, child_path, child) in children(value, &path) {
+        push_rows(child, child_key, child_path, depth + 1, collapsed, rows);
+    }
+    rows.push(TreeRow { depth, path, key: None, kind: RowKind::Close(close) });
+}
+
+fn collect_containers(value: &Value, path: String, out: &mut Vec<String>) {
+    if !value.is_object() && !value.is_array() {
+        return;
+    }
+    for (_, child_path, child) in children(value, &path) {
+        collect_containers(child, child_path, out);
+    }
+    out.push(path);
+}
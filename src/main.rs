@@ -1,43 +1,60 @@
 mod app;
+mod binary;
+mod codegen;
+mod curl;
+mod files;
+mod graphql;
+mod highlight;
 mod http;
+mod jq;
+mod json_tree;
 mod models;
+mod search;
+mod storage;
+mod template;
 mod ui;
 mod utils;
 
 use std::time::Duration;
 
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers,
+};
 use ratatui::DefaultTerminal;
 use tokio::sync::mpsc;
 
-use app::{App, EditFocus, Panel, RequestTab};
-use http::{HttpResult, RequestData};
-
-const HELP_LINES: usize = 24;
+use app::{App, EditFocus, Panel, PromptKind, RequestTab, ResponseTab, Ticket};
+use graphql::Schema;
+use http::HttpResult;
 
 fn main() -> Result<()> {
     let mut terminal = ratatui::init();
+    // Bracketed paste delivers a pasted curl command as one event instead of keystrokes
+    let _ = crossterm::execute!(std::io::stdout(), EnableBracketedPaste);
     let result = run(&mut terminal);
+    let _ = crossterm::execute!(std::io::stdout(), DisableBracketedPaste);
     ratatui::restore();
     result
 }
 
 fn run(terminal: &mut DefaultTerminal) -> Result<()> {
     let rt = tokio::runtime::Runtime::new()?;
-    let (tx, mut rx) = mpsc::unbounded_channel::<HttpResult>();
-    let client = http::build_client()?;
+    let (tx, mut rx) = mpsc::unbounded_channel::<(Ticket, HttpResult)>();
+    let (schema_tx, mut schema_rx) = mpsc::unbounded_channel::<(String, Result<Schema, String>)>();
+    let mut clients = http::ClientCache::default();
     let mut app = App::new();
 
     loop {
         terminal.draw(|frame| ui::render(frame, &mut app))?;
 
         // Check for HTTP responses
-        if let Ok(result) = rx.try_recv() {
-            match result {
-                HttpResult::Success(response) => app.set_response(response),
-                HttpResult::Error(err) => app.set_error(err),
-            }
+        while let Ok((ticket, result)) = rx.try_recv() {
+            app.handle_http_result(ticket, result);
+        }
+        while let Ok((url, schema)) = schema_rx.try_recv() {
+            app.set_schema(url, schema);
         }
 
         if !event::poll(Duration::from_millis(50))? {
@@ -45,18 +62,37 @@ fn run(terminal: &mut DefaultTerminal) -> Result<()> {
         }
 
         let event = event::read()?;
+        if let Event::Paste(text) = &event {
+            handle_paste(&mut app, text);
+            continue;
+        }
         let Event::Key(key) = event else { continue };
         if key.kind != KeyEventKind::Press {
             continue;
         }
 
         let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        app.message = None;
+
+        // Code export overlay
+        if app.export.is_some() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => app.close_export(),
+                KeyCode::Tab | KeyCode::Char('l') | KeyCode::Right => app.export_switch_language(true),
+                KeyCode::BackTab | KeyCode::Char('h') | KeyCode::Left => app.export_switch_language(false),
+                KeyCode::Char('j') | KeyCode::Down => app.export_scroll(1),
+                KeyCode::Char('k') | KeyCode::Up => app.export_scroll(-1),
+                KeyCode::Char('y') => app.copy_export(),
+                _ => {}
+            }
+            continue;
+        }
 
         // Help overlay
         if app.show_help {
             match key.code {
                 KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => app.show_help = false,
-                KeyCode::Char('j') | KeyCode::Down => app.help_scroll_down(1, HELP_LINES),
+                KeyCode::Char('j') | KeyCode::Down => app.help_scroll_down(1, ui::HELP_LINES.len()),
                 KeyCode::Char('k') | KeyCode::Up => app.help_scroll_up(1),
                 _ => {}
             }
@@ -66,23 +102,36 @@ fn run(terminal: &mut DefaultTerminal) -> Result<()> {
         // Global shortcuts
         match key.code {
             KeyCode::Char('s') if ctrl => {
-                send_request(&rt, &mut app, tx.clone(), client.clone());
+                send_request(&rt, &mut app, tx.clone(), &mut clients);
                 continue;
             }
             KeyCode::Char('c') if ctrl => {
                 app.quit();
                 continue;
             }
+            KeyCode::Char('g') if ctrl && app.body_type == models::BodyType::GraphQl => {
+                fetch_schema(&rt, &mut app, schema_tx.clone(), &mut clients);
+                continue;
+            }
+            // Only while loading, so Ctrl+X still cuts text in the editors otherwise
+            KeyCode::Char('x') if ctrl && app.is_loading() => {
+                app.cancel_request();
+                continue;
+            }
             _ => {}
         }
 
         // Handle based on edit focus
         match app.edit_focus {
+            EditFocus::None if app.show_environments => handle_environments(&mut app, key.code),
+            EditFocus::None if app.show_settings => handle_settings(&mut app, key.code),
+            EditFocus::None if app.show_cookies => handle_cookies(&mut app, key.code),
             EditFocus::None => handle_normal_mode(&mut app, key.code, ctrl),
             EditFocus::Url => handle_url_edit(&mut app, key),
             EditFocus::KeyValue => handle_kv_edit(&mut app, key, ctrl),
             EditFocus::Body => handle_body_edit(&mut app, key, ctrl),
             EditFocus::Auth => handle_auth_edit(&mut app, key),
+            EditFocus::Prompt => handle_prompt_edit(&mut app, key),
         }
 
         if app.should_quit {
@@ -94,11 +143,25 @@ fn run(terminal: &mut DefaultTerminal) -> Result<()> {
 }
 
 fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
+    if app.focused_panel == Panel::Response && app.tree_view_active() && handle_json_tree(app, code, ctrl) {
+        return;
+    }
+
     match code {
+        KeyCode::Esc if app.is_loading() => app.cancel_request(),
         KeyCode::Char('q') | KeyCode::Esc => app.quit(),
+        KeyCode::Char('?') if app.focused_panel == Panel::Response && app.response_tab == ResponseTab::Body => {
+            app.start_search(true);
+        }
         KeyCode::Char('?') => app.toggle_help(),
 
-        // Auth type cycling (must come before general Tab handling)
+        // Environments, settings & cookies
+        KeyCode::Char('E') => app.toggle_environments(),
+        KeyCode::Char('S') => app.toggle_settings(),
+        KeyCode::Char('C') => app.toggle_cookies(),
+        KeyCode::Char('e') if ctrl => app.cycle_active_environment(),
+
+        // Auth and body type cycling (must come before general Tab handling)
         KeyCode::Tab
             if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Auth =>
         {
@@ -109,6 +172,26 @@ fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
         {
             app.cycle_auth_type_prev();
         }
+        KeyCode::Char('g')
+            if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Auth =>
+        {
+            app.cycle_auth_option();
+        }
+        KeyCode::Char('x')
+            if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Auth =>
+        {
+            app.forget_oauth_token();
+        }
+        KeyCode::Tab
+            if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Body =>
+        {
+            app.cycle_body_type(true);
+        }
+        KeyCode::BackTab
+            if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Body =>
+        {
+            app.cycle_body_type(false);
+        }
 
         // Panel navigation
         KeyCode::Tab => app.focus_next_panel(),
@@ -117,6 +200,10 @@ fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
         KeyCode::Char('l') | KeyCode::Right => app.focus_next_panel(),
 
         // Tab switching
+        KeyCode::Char('1') if app.focused_panel == Panel::Response => app.set_response_tab(ResponseTab::Body),
+        KeyCode::Char('2') if app.focused_panel == Panel::Response => app.set_response_tab(ResponseTab::Headers),
+        KeyCode::Char('3') if app.focused_panel == Panel::Response => app.set_response_tab(ResponseTab::Cookies),
+        KeyCode::Char('4') if app.focused_panel == Panel::Response => app.set_response_tab(ResponseTab::Timing),
         KeyCode::Char('1') => app.active_tab = RequestTab::Params,
         KeyCode::Char('2') => app.active_tab = RequestTab::Headers,
         KeyCode::Char('3') => app.active_tab = RequestTab::Body,
@@ -137,8 +224,23 @@ fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
         // Response scrolling
         KeyCode::Char('g') if app.focused_panel == Panel::Response => app.response_scroll_top(),
         KeyCode::Char('G') if app.focused_panel == Panel::Response => {
-            app.response_scroll_bottom(response_lines(app))
+            app.response_scroll_bottom(app.response_line_count())
+        }
+        KeyCode::Char('t') if app.focused_panel == Panel::Response => app.toggle_tree_view(),
+        KeyCode::Char('x') if app.focused_panel == Panel::Response => app.toggle_hex_view(),
+        KeyCode::Char('w') if app.focused_panel == Panel::Response => app.open_prompt(PromptKind::SaveBody),
+        KeyCode::Char('p') if app.focused_panel == Panel::Response && app.socket_open() => app.ping_socket(),
+        KeyCode::Char('f') if app.focused_panel == Panel::Response => {
+            app.open_prompt(PromptKind::ResponseFilter);
         }
+        KeyCode::Char('/')
+            if app.focused_panel == Panel::Response && app.response_tab == ResponseTab::Headers =>
+        {
+            app.open_prompt(PromptKind::HeaderFilter);
+        }
+        KeyCode::Char('/') if app.focused_panel == Panel::Response => app.start_search(false),
+        KeyCode::Char('n') if app.focused_panel == Panel::Response => app.search_next(false),
+        KeyCode::Char('N') if app.focused_panel == Panel::Response => app.search_next(true),
         KeyCode::Char('d') if ctrl && app.focused_panel == Panel::Response => {
             // Scroll down by 10 lines
             for _ in 0..10 {
@@ -159,28 +261,62 @@ fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
         KeyCode::Char('d') if app.focused_panel == Panel::Sidebar => {
             app.delete_selected_request();
         }
+        KeyCode::Enter | KeyCode::Char(' ')
+            if app.focused_panel == Panel::Sidebar && app.selected_is_folder() =>
+        {
+            app.toggle_selected_folder();
+        }
         KeyCode::Enter if app.focused_panel == Panel::Sidebar => {
             app.load_selected_request();
             app.focused_panel = Panel::RequestEditor;
         }
+        KeyCode::Char('f') if app.focused_panel == Panel::Sidebar => {
+            app.open_prompt(PromptKind::NewFolder);
+        }
+        KeyCode::Char('r') if app.focused_panel == Panel::Sidebar => {
+            app.open_prompt(PromptKind::Rename);
+        }
+        KeyCode::Char('m') if app.focused_panel == Panel::Sidebar => {
+            app.toggle_move_mark();
+        }
+        KeyCode::Char('p') if app.focused_panel == Panel::Sidebar => {
+            app.move_marked_request();
+        }
+        KeyCode::Char('I') if app.focused_panel == Panel::Sidebar => {
+            app.open_prompt(PromptKind::ImportCurl);
+        }
 
         // Request editor actions
+        KeyCode::Char('c') if app.focused_panel == Panel::RequestEditor => {
+            app.open_export();
+        }
         KeyCode::Char('i') if app.focused_panel == Panel::RequestEditor => {
             app.start_editing(EditFocus::Url);
         }
+        #[allow(clippy::collapsible_match)]
         KeyCode::Char('a') if app.focused_panel == Panel::RequestEditor => {
-            if app.active_tab != RequestTab::Body {
+            if app.tab_is_kv() {
                 app.kv_add();
                 app.start_editing(EditFocus::KeyValue);
             }
         }
         KeyCode::Char('e')
-            if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Body =>
+            if app.focused_panel == Panel::RequestEditor && app.active_tab == RequestTab::Body
+                && app.body_type == models::BodyType::File =>
+        {
+            app.open_prompt(PromptKind::BodyFile);
+        }
+        KeyCode::Char('e')
+            if app.focused_panel == Panel::RequestEditor && !app.tab_is_kv() =>
         {
             app.start_editing(EditFocus::Body);
         }
         KeyCode::Enter if app.focused_panel == Panel::RequestEditor => match app.active_tab {
-            RequestTab::Body => app.start_editing(EditFocus::Body),
+            RequestTab::Body if app.body_type == models::BodyType::File => {
+                app.open_prompt(PromptKind::BodyFile);
+            }
+            RequestTab::Body if !app.body_type.is_form() => app.start_editing(EditFocus::Body),
+            #[allow(clippy::collapsible_match)]
             RequestTab::Auth => {
                 if !matches!(app.auth, models::AuthType::None) {
                     app.start_editing(EditFocus::Auth);
@@ -192,12 +328,12 @@ fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
             _ => {}
         },
         KeyCode::Char('d')
-            if app.focused_panel == Panel::RequestEditor && app.active_tab != RequestTab::Body =>
+            if app.focused_panel == Panel::RequestEditor && app.tab_is_kv() =>
         {
             app.kv_delete();
         }
         KeyCode::Char(' ')
-            if app.focused_panel == Panel::RequestEditor && app.active_tab != RequestTab::Body =>
+            if app.focused_panel == Panel::RequestEditor && app.tab_is_kv() =>
         {
             app.kv_toggle_enabled();
         }
@@ -206,11 +342,102 @@ fn handle_normal_mode(app: &mut App, code: KeyCode, ctrl: bool) {
     }
 }
 
+/// Cursor movement and vim-style folds in the JSON tree. Returns whether the
+/// key was consumed; anything else falls through to normal mode.
+fn handle_json_tree(app: &mut App, code: KeyCode, ctrl: bool) -> bool {
+    let fold_pending = std::mem::take(&mut app.fold_pending);
+    let Some(tree) = app.json_tree.as_mut() else { return false };
+
+    if fold_pending {
+        match code {
+            KeyCode::Char('a') => tree.set_expanded(None),
+            KeyCode::Char('o') => tree.set_expanded(Some(true)),
+            KeyCode::Char('c') => tree.set_expanded(Some(false)),
+            KeyCode::Char('R') => tree.expand_all(),
+            KeyCode::Char('M') => tree.collapse_all(),
+            _ => {}
+        }
+        return true;
+    }
+
+    match code {
+        KeyCode::Char('j') | KeyCode::Down => tree.move_cursor(1),
+        KeyCode::Char('k') | KeyCode::Up => tree.move_cursor(-1),
+        KeyCode::Char('d') if ctrl => tree.move_cursor(10),
+        KeyCode::Char('u') if ctrl => tree.move_cursor(-10),
+        KeyCode::Char('g') => tree.cursor_first(),
+        KeyCode::Char('G') => tree.cursor_last(),
+        KeyCode::Enter | KeyCode::Char(' ') => tree.set_expanded(None),
+        KeyCode::Char('z') => app.fold_pending = true,
+        _ => return false,
+    }
+    true
+}
+
+fn handle_environments(app: &mut App, code: KeyCode) {
+    let has_env = app.environment_cursor < app.environments.len();
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('E') => app.toggle_environments(),
+        KeyCode::Tab | KeyCode::Char(']') => app.environment_next(),
+        KeyCode::BackTab | KeyCode::Char('[') => app.environment_prev(),
+        KeyCode::Char('n') => app.open_prompt(PromptKind::NewEnvironment),
+        KeyCode::Char('r') => app.open_prompt(PromptKind::RenameEnvironment),
+        KeyCode::Char('D') => app.delete_cursor_environment(),
+        KeyCode::Char('s') => app.activate_cursor_environment(),
+
+        // Variables
+        KeyCode::Char('j') | KeyCode::Down => app.kv_select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app.kv_select_prev(),
+        KeyCode::Char('a') if has_env => {
+            app.kv_add();
+            app.start_editing(EditFocus::KeyValue);
+        }
+        KeyCode::Enter if has_env && !app.current_kv_items().is_empty() => {
+            app.start_editing(EditFocus::KeyValue);
+        }
+        KeyCode::Char('d') if has_env => app.kv_delete(),
+        KeyCode::Char(' ') if has_env => app.kv_toggle_enabled(),
+        _ => {}
+    }
+}
+
+fn handle_settings(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('S') => app.toggle_settings(),
+        KeyCode::Tab | KeyCode::BackTab => app.switch_settings_scope(),
+        KeyCode::Char('j') | KeyCode::Down => app.settings_select(1),
+        KeyCode::Char('k') | KeyCode::Up => app.settings_select(-1),
+        KeyCode::Char(' ') => app.toggle_selected_setting(),
+        KeyCode::Enter if app.selected_setting().is_toggle() => app.toggle_selected_setting(),
+        KeyCode::Enter => app.open_prompt(PromptKind::EditSetting),
+        KeyCode::Char('d') => app.reset_selected_setting(),
+        _ => {}
+    }
+}
+
+fn handle_cookies(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => app.toggle_cookies(),
+        KeyCode::Char('j') | KeyCode::Down => app.cookies_select(1),
+        KeyCode::Char('k') | KeyCode::Up => app.cookies_select(-1),
+        KeyCode::Enter => app.open_prompt(PromptKind::EditCookie),
+        KeyCode::Char('d') => app.delete_selected_cookie(),
+        KeyCode::Char('D') => app.clear_selected_cookie_domain(),
+        KeyCode::Char('x') => app.clear_cookies(),
+        _ => {}
+    }
+}
+
 fn handle_url_edit(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.stop_editing(),
         KeyCode::Tab => app.cycle_method_next(),
         KeyCode::BackTab => app.cycle_method_prev(),
+        KeyCode::Enter if is_curl_command(app.url()) => {
+            let command = app.url().to_string();
+            app.import_curl_into_editor(&command);
+            app.stop_editing();
+        }
         KeyCode::Enter => {} // Ignore enter for single-line input
         _ => {
             app.url_input.input(key);
@@ -240,11 +467,14 @@ fn handle_kv_edit(app: &mut App, key: KeyEvent, ctrl: bool) {
 }
 
 fn handle_body_edit(app: &mut App, key: KeyEvent, ctrl: bool) {
+    let graphql = app.body_type == models::BodyType::GraphQl;
     match key.code {
         KeyCode::Esc => app.stop_editing(),
         KeyCode::Char('f') if ctrl => app.format_json(),
+        KeyCode::Tab if graphql && !app.editing_variables => app.complete_query(),
+        KeyCode::BackTab if graphql => app.toggle_variables_editor(),
         _ => {
-            app.body_editor.input(key);
+            app.active_body_editor().input(key);
         }
     }
 }
@@ -252,12 +482,66 @@ fn handle_body_edit(app: &mut App, key: KeyEvent, ctrl: bool) {
 fn handle_auth_edit(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.stop_editing(),
-        KeyCode::Tab | KeyCode::BackTab if app.auth.has_two_fields() => {
-            app.auth_editor.toggle_field();
+        KeyCode::Tab => app.auth_editor.next_field(),
+        KeyCode::BackTab => app.auth_editor.prev_field(),
+        KeyCode::Enter => {}
+        _ => {
+            if let Some(input) = app.auth_editor.current_input_mut() {
+                input.input(key);
+            }
+        }
+    }
+}
+
+fn is_curl_command(text: &str) -> bool {
+    text.trim_start().starts_with("curl ")
+}
+
+fn handle_paste(app: &mut App, text: &str) {
+    match app.edit_focus {
+        EditFocus::Url if is_curl_command(text) => {
+            app.import_curl_into_editor(text);
+            app.stop_editing();
+        }
+        EditFocus::Url => {
+            app.url_input.insert_str(utils::join_lines(text));
+        }
+        EditFocus::KeyValue => {
+            app.current_kv_editor_mut().current_input_mut().insert_str(utils::join_lines(text));
+        }
+        EditFocus::Body => {
+            app.active_body_editor().insert_str(text);
+        }
+        EditFocus::Auth => {
+            if let Some(input) = app.auth_editor.current_input_mut() {
+                input.insert_str(utils::join_lines(text));
+            }
+        }
+        EditFocus::Prompt => {
+            if let Some(prompt) = app.prompt.as_mut() {
+                prompt.input.insert_str(utils::join_lines(text));
+            }
         }
-        KeyCode::Tab | KeyCode::BackTab | KeyCode::Enter => {}
+        // Pasting a curl command anywhere else imports it as a new request
+        EditFocus::None if is_curl_command(text) => app.import_curl_as_new(text),
+        EditFocus::None => {}
+    }
+}
+
+fn handle_prompt_edit(app: &mut App, key: KeyEvent) {
+    let Some(prompt) = app.prompt.as_mut() else {
+        app.cancel_prompt();
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => app.cancel_prompt(),
+        KeyCode::Enter => app.submit_prompt(),
+        KeyCode::Tab if prompt.kind.is_path() => app.complete_prompt_path(),
         _ => {
-            app.auth_editor.current_input_mut().input(key);
+            prompt.input.input(key);
+            if prompt.kind.is_search() {
+                app.update_search();
+            }
         }
     }
 }
@@ -265,9 +549,14 @@ fn handle_auth_edit(app: &mut App, key: KeyEvent) {
 fn send_request(
     rt: &tokio::runtime::Runtime,
     app: &mut App,
-    tx: mpsc::UnboundedSender<HttpResult>,
-    client: http::Client,
+    tx: mpsc::UnboundedSender<(Ticket, HttpResult)>,
+    clients: &mut http::ClientCache,
 ) {
+    // Once a socket is open, sending sends the body as a message
+    if app.socket_open() {
+        app.send_socket_message();
+        return;
+    }
     if app.is_loading() {
         return;
     }
@@ -280,38 +569,75 @@ fn send_request(
 
     // Save or update request in history
     let request = models::Request {
-        method: app.method,
-        url: url.clone(),
         params: app.params.clone(),
         headers: app.headers.clone(),
         body: app.body(),
+        body_type: app.body_type,
+        form: app.form.clone(),
+        body_file: app.body_file.clone(),
+        variables: app.variables(),
         auth: app.auth.clone(),
-        created_at: std::time::SystemTime::now(),
+        settings: app.request_settings.clone(),
+        ..models::Request::new(app.method, url.clone())
     };
 
-    match app.editing_request_idx {
-        Some(idx) => app.update_request(idx, request),
+    match app.editing_request {
+        Some(id) => app.update_request(id, request),
         None => app.add_request(request),
     }
 
-    let data = RequestData {
-        method: app.method,
-        url,
-        params: app.params.clone(),
-        headers: app.headers.clone(),
-        body: app.body(),
-        auth: app.auth.clone(),
+    // Unresolved {{variables}} are reported rather than sent literally
+    let data = match app.request_data() {
+        Ok(data) => data,
+        Err(err) => {
+            app.set_error(err);
+            return;
+        }
     };
 
-    app.set_loading();
-    rt.spawn(async move {
-        http::send_request(client, data, tx).await;
+    let client = match clients.get(&data.settings) {
+        Ok(client) => client,
+        Err(err) => {
+            app.set_error(err);
+            return;
+        }
+    };
+
+    if data.method == models::HttpMethod::Ws {
+        let Some(ticket) = app.next_ticket() else { return };
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        let handle = rt.spawn(http::open_socket(client, data, ticket, tx, commands_rx));
+        app.set_loading(ticket, handle.abort_handle(), Some(commands));
+        return;
+    }
+
+    let Some(ticket) = app.next_ticket() else { return };
+    let handle = rt.spawn(async move {
+        http::send_request(client, data, ticket, tx).await;
     });
+    app.set_loading(ticket, handle.abort_handle(), None);
 }
 
-fn response_lines(app: &App) -> usize {
-    match &app.request_state {
-        models::RequestState::Success(resp) => resp.line_count(),
-        _ => 0,
-    }
+/// Introspect the GraphQL schema of the open request's endpoint, sent with
+/// its headers and auth.
+fn fetch_schema(
+    rt: &tokio::runtime::Runtime,
+    app: &mut App,
+    tx: mpsc::UnboundedSender<(String, Result<Schema, String>)>,
+    clients: &mut http::ClientCache,
+) {
+    let data = match app.request_data() {
+        Ok(data) => data,
+        Err(err) => return app.show_error(err),
+    };
+    let client = match clients.get(&data.settings) {
+        Ok(client) => client,
+        Err(err) => return app.show_error(err),
+    };
+
+    let url = data.url.clone();
+    app.show_info(format!("Fetching GraphQL schema from {}", url));
+    rt.spawn(async move {
+        let _ = tx.send((url, graphql::introspect(client, data).await));
+    });
 }
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use super::request::{Request, RequestId};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Node {
+    Folder(Folder),
+    Request(Box<Request>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub name: String,
+    #[serde(default)]
+    pub expanded: bool,
+    #[serde(default)]
+    pub items: Vec<Node>,
+}
+
+impl Folder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            expanded: true,
+            items: vec![],
+        }
+    }
+}
+
+/// A sidebar row: the node's location in the tree and its nesting depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub path: Vec<usize>,
+    pub depth: usize,
+}
+
+/// The tree of folders and requests shown in the sidebar. Top-level folders
+/// act as named collections; requests may also live at the root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Collection {
+    #[serde(default)]
+    pub items: Vec<Node>,
+}
+
+impl Collection {
+    /// Give every request without an ID (e.g. from older data files) a fresh one.
+    pub fn assign_ids(&mut self) {
+        let mut next = self.max_id() + 1;
+        visit_requests_mut(&mut self.items, &mut |req| {
+            if req.id == RequestId::default() {
+                req.id = RequestId(next);
+                next += 1;
+            }
+        });
+    }
+
+    fn max_id(&self) -> u64 {
+        let mut max = 0;
+        visit_requests(&self.items, &mut |req| max = max.max(req.id.0));
+        max
+    }
+
+    pub fn request_count(&self) -> usize {
+        let mut count = 0;
+        visit_requests(&self.items, &mut |_| count += 1);
+        count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Rows for every node not hidden inside a collapsed folder, in display order.
+    pub fn visible_rows(&self) -> Vec<Row> {
+        let mut rows = vec![];
+        collect_rows(&self.items, &mut vec![], &mut rows);
+        rows
+    }
+
+    pub fn node(&self, path: &[usize]) -> Option<&Node> {
+        let (&last, parents) = path.split_last()?;
+        self.children(parents)?.get(last)
+    }
+
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        let (&last, parents) = path.split_last()?;
+        self.children_mut(parents)?.get_mut(last)
+    }
+
+    /// Items of the folder at `path`, or the root items for an empty path.
+    fn children(&self, path: &[usize]) -> Option<&Vec<Node>> {
+        let mut items = &self.items;
+        for &i in path {
+            match items.get(i)? {
+                Node::Folder(folder) => items = &folder.items,
+                Node::Request(_) => return None,
+            }
+        }
+        Some(items)
+    }
+
+    fn children_mut(&mut self, path: &[usize]) -> Option<&mut Vec<Node>> {
+        let mut items = &mut self.items;
+        for &i in path {
+            match items.get_mut(i)? {
+                Node::Folder(folder) => items = &mut folder.items,
+                Node::Request(_) => return None,
+            }
+        }
+        Some(items)
+    }
+
+    /// Insert `node` at the top of the folder at `parent`, returning its path.
+    /// A request without an ID is assigned one.
+    pub fn insert(&mut self, parent: &[usize], mut node: Node) -> Option<Vec<usize>> {
+        if let Node::Request(req) = &mut node
+            && req.id == RequestId::default()
+        {
+            req.id = RequestId(self.max_id() + 1);
+        }
+        self.children_mut(parent)?.insert(0, node);
+
+        let mut path = parent.to_vec();
+        path.push(0);
+        Some(path)
+    }
+
+    /// Move a request to the top of the folder at `target`, returning its new path.
+    pub fn move_request(&mut self, id: RequestId, target: &[usize]) -> Option<Vec<usize>> {
+        let from = self.path_of(id)?;
+        self.children(target)?;
+
+        // Removing the request shifts later siblings up; adjust the target to match.
+        let (&idx, parent) = from.split_last()?;
+        let mut target = target.to_vec();
+        if target.len() > parent.len() && target.starts_with(parent) && target[parent.len()] > idx {
+            target[parent.len()] -= 1;
+        }
+
+        let node = self.remove(&from)?;
+        self.insert(&target, node)
+    }
+
+    pub fn remove(&mut self, path: &[usize]) -> Option<Node> {
+        let (&last, parents) = path.split_last()?;
+        let items = self.children_mut(parents)?;
+        (last < items.len()).then(|| items.remove(last))
+    }
+
+    pub fn find_request_mut(&mut self, id: RequestId) -> Option<&mut Request> {
+        let path = self.path_of(id)?;
+        match self.node_mut(&path)? {
+            Node::Request(req) => Some(req),
+            Node::Folder(_) => None,
+        }
+    }
+
+    pub fn path_of(&self, id: RequestId) -> Option<Vec<usize>> {
+        find_path(&self.items, id, &mut vec![])
+    }
+}
+
+fn collect_rows(items: &[Node], path: &mut Vec<usize>, rows: &mut Vec<Row>) {
+    for (i, node) in items.iter().enumerate() {
+        path.push(i);
+        rows.push(Row { path: path.clone(), depth: path.len() - 1 });
+        if let Node::Folder(folder) = node
+            && folder.expanded
+        {
+            collect_rows(&folder.items, path, rows);
+        }
+        path.pop();
+    }
+}
+
+fn find_path(items: &[Node], id: RequestId, path: &mut Vec<usize>) -> Option<Vec<usize>> {
+    for (i, node) in items.iter().enumerate() {
+        path.push(i);
+        let found = match node {
+            Node::Request(req) if req.id == id => Some(path.clone()),
+            Node::Request(_) => None,
+            Node::Folder(folder) => find_path(&folder.items, id, path),
+        };
+        path.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+fn visit_requests(items: &[Node], f: &mut impl FnMut(&Request)) {
+    for node in items {
+        match node {
+            Node::Request(req) => f(req),
+            Node::Folder(folder) => visit_requests(&folder.items, f),
+        }
+    }
+}
+
+fn visit_requests_mut(items: &mut [Node], f: &mut impl FnMut(&mut Request)) {
+    for node in items {
+        match node {
+            Node::Request(req) => f(req),
+            Node::Folder(folder) => visit_requests_mut(&mut folder.items, f),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// A cookie as set by a server's `Set-Cookie` header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    pub expires: Option<String>,
+    pub max_age: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<String>,
+    /// Set without a `Domain` attribute: sent to exactly this host only.
+    #[serde(default)]
+    pub host_only: bool,
+    /// When a stored cookie lapses, from `Max-Age` or `Expires`. `None` lasts
+    /// until cleared.
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
+}
+
+impl Cookie {
+    /// Parse a `Set-Cookie` header value. Returns `None` without a `name=value` pair.
+    pub fn parse_set_cookie(header: &str) -> Option<Self> {
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            domain: String::new(),
+            path: String::new(),
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            host_only: false,
+            expires_at: None,
+        };
+
+        for attr in parts {
+            let (key, val) = match attr.split_once('=') {
+                Some((k, v)) => (k.trim(), v.trim()),
+                None => (attr.trim(), ""),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "domain" => cookie.domain = val.trim_start_matches('.').to_ascii_lowercase(),
+                "path" => cookie.path = val.to_string(),
+                "expires" => cookie.expires = Some(val.to_string()),
+                "max-age" => cookie.max_age = val.parse().ok(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => cookie.same_site = Some(val.to_string()),
+                _ => {}
+            }
+        }
+
+        Some(cookie)
+    }
+
+    /// Attributes in `Set-Cookie` syntax, for display.
+    pub fn attributes(&self) -> Vec<String> {
+        let mut attrs = vec![];
+        if !self.domain.is_empty() {
+            attrs.push(format!("Domain={}", self.domain));
+        }
+        if !self.path.is_empty() {
+            attrs.push(format!("Path={}", self.path));
+        }
+        if let Some(expires) = &self.expires {
+            attrs.push(format!("Expires={}", expires));
+        }
+        if let Some(max_age) = self.max_age {
+            attrs.push(format!("Max-Age={}", max_age));
+        }
+        if self.secure {
+            attrs.push("Secure".to_string());
+        }
+        if self.http_only {
+            attrs.push("HttpOnly".to_string());
+        }
+        if let Some(same_site) = &self.same_site {
+            attrs.push(format!("SameSite={}", same_site));
+        }
+        attrs
+    }
+}
+
+/// Cookies received from servers, sent back on matching requests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Cookies ordered by domain, then path, then name.
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.cookies
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// Store the `Set-Cookie` headers of a response to `host` + `path`.
+    pub fn store<'a>(&mut self, host: &str, path: &str, set_cookies: impl Iterator<Item = &'a str>) {
+        let now = SystemTime::now();
+        for header in set_cookies {
+            let Some(mut cookie) = Cookie::parse_set_cookie(header) else { continue };
+
+            if cookie.domain.is_empty() {
+                cookie.domain = host.to_ascii_lowercase();
+                cookie.host_only = true;
+            } else if !domain_matches(host, &cookie.domain) {
+                continue; // A server may only set cookies for itself or a parent domain
+            }
+            if !cookie.path.starts_with('/') {
+                cookie.path = default_path(path);
+            }
+            cookie.expires_at = match (cookie.max_age, &cookie.expires) {
+                (Some(secs), _) if secs <= 0 => Some(UNIX_EPOCH),
+                (Some(secs), _) => Some(now + Duration::from_secs(secs as u64)),
+                (None, Some(date)) => parse_http_date(date),
+                (None, None) => None,
+            };
+
+            self.cookies.retain(|c| {
+                !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+            });
+            if cookie.expires_at.is_none_or(|at| at > now) {
+                self.cookies.push(cookie);
+            }
+        }
+        self.cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+    }
+
+    /// `Cookie` header value for a request, or `None` if nothing matches.
+    pub fn header_for(&self, host: &str, path: &str, secure: bool) -> Option<String> {
+        let now = SystemTime::now();
+        let host = host.to_ascii_lowercase();
+        let pairs: Vec<String> = self
+            .cookies
+            .iter()
+            .filter(|c| if c.host_only { c.domain == host } else { domain_matches(&host, &c.domain) })
+            .filter(|c| path_matches(path, &c.path))
+            .filter(|c| secure || !c.secure)
+            .filter(|c| c.expires_at.is_none_or(|at| at > now))
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+
+    pub fn set_value(&mut self, index: usize, value: String) {
+        if let Some(cookie) = self.cookies.get_mut(index) {
+            cookie.value = value;
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.cookies.len() {
+            self.cookies.remove(index);
+        }
+    }
+
+    pub fn clear_domain(&mut self, domain: &str) {
+        self.cookies.retain(|c| c.domain != domain);
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+}
+
+/// RFC 6265 domain matching: the host itself or a subdomain of `domain`.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    host == domain || host.strip_suffix(domain).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || request_path.strip_prefix(cookie_path).is_some_and(|rest| {
+            cookie_path.ends_with('/') || rest.starts_with('/')
+        })
+}
+
+/// The directory of the request path, used when a cookie sets no `Path`.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => path[..i].to_string(),
+    }
+}
+
+/// Parse the date formats seen in `Expires`, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`
+/// and the older `Wed, 21-Oct-15 07:28:00 GMT`.
+fn parse_http_date(date: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+    let (mut day, mut month, mut year, mut time) = (None, None, None, None);
+    for token in date.split([' ', ',', '-']).filter(|t| !t.is_empty()) {
+        let lower = token.to_ascii_lowercase();
+        if token.contains(':') {
+            let parts: Vec<u64> = token.split(':').filter_map(|p| p.parse().ok()).collect();
+            if let [h, m, s] = parts[..] {
+                time = Some(h * 3600 + m * 60 + s);
+            }
+        } else if let Some(i) = MONTHS.iter().position(|m| lower.starts_with(m)) {
+            month = Some(i as u64 + 1);
+        } else if let Ok(n) = token.parse::<u64>() {
+            match (day, token.len()) {
+                (None, 1 | 2) => day = Some(n),
+                (_, 2) => year = Some(if n < 70 { 2000 + n } else { 1900 + n }),
+                _ => year = Some(n),
+            }
+        }
+    }
+
+    let (day, month, year) = (day?, month?, year?);
+    // Days since the epoch for a proleptic Gregorian date
+    let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * m + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146097 + doe).checked_sub(719468)?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + time.unwrap_or(0)))
+}
@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use super::cookie::CookieJar;
+use super::request::KeyValue;
+
+/// Identifies an environment while the app runs; not saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvironmentId(u64);
+
+impl EnvironmentId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A named set of variables substituted into `{{name}}` references.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    #[serde(skip, default = "EnvironmentId::next")]
+    pub id: EnvironmentId,
+    pub name: String,
+    #[serde(default)]
+    pub variables: Vec<KeyValue>,
+    /// Cookies received while this environment was active.
+    #[serde(default, skip_serializing_if = "CookieJar::is_empty")]
+    pub cookies: CookieJar,
+}
+
+impl Environment {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            id: EnvironmentId::next(),
+            name: name.into(),
+            variables: vec![],
+            cookies: CookieJar::default(),
+        }
+    }
+}
@@ -1,5 +1,17 @@
+mod collection;
+mod cookie;
+mod environment;
 mod request;
 mod response;
+mod settings;
+mod stream;
 
-pub use request::{AuthType, HttpMethod, KeyValue, Request};
-pub use response::{RequestState, Response};
+pub use collection::{Collection, Folder, Node, Row};
+pub use cookie::CookieJar;
+pub use environment::{Environment, EnvironmentId};
+pub use request::{
+    AuthType, AwsCredentials, BodyType, Grant, HttpMethod, KeyLocation, KeyValue, OAuthConfig, Request, RequestId,
+};
+pub use response::{decode_body, format_duration, RequestState, Response, SpillFile};
+pub use settings::{ClientSettings, Setting, Settings, SettingsOverride};
+pub use stream::{EventLog, FrameKind, SseEvent, SseParser, StreamEvent, WsFrame};
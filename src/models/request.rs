@@ -1,6 +1,10 @@
 use std::time::SystemTime;
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+use super::settings::SettingsOverride;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyValue {
     pub enabled: bool,
     pub key: String,
@@ -17,7 +21,8 @@ impl Default for KeyValue {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum HttpMethod {
     #[default]
     Get,
@@ -27,15 +32,105 @@ pub enum HttpMethod {
     Delete,
     Head,
     Options,
+    /// A WebSocket connection rather than an HTTP request.
+    Ws,
+}
+
+/// How the body is sent: raw text with a content type, or form fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyType {
+    /// Raw text; JSON if it looks like JSON, otherwise plain text.
+    #[default]
+    Auto,
+    Json,
+    Xml,
+    Html,
+    Text,
+    /// `application/x-www-form-urlencoded` fields.
+    Form,
+    /// `multipart/form-data` fields; a value of `@path` uploads that file.
+    Multipart,
+    /// A file streamed from disk.
+    File,
+    /// A GraphQL query, sent with its variables as JSON.
+    GraphQl,
+}
+
+impl BodyType {
+    pub const ALL: [BodyType; 9] = [
+        BodyType::Auto,
+        BodyType::Json,
+        BodyType::Xml,
+        BodyType::Html,
+        BodyType::Text,
+        BodyType::Form,
+        BodyType::Multipart,
+        BodyType::File,
+        BodyType::GraphQl,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BodyType::Auto => "Raw (auto)",
+            BodyType::Json => "Raw JSON",
+            BodyType::Xml => "Raw XML",
+            BodyType::Html => "Raw HTML",
+            BodyType::Text => "Raw text",
+            BodyType::Form => "Form URL-encoded",
+            BodyType::Multipart => "Multipart form",
+            BodyType::File => "File",
+            BodyType::GraphQl => "GraphQL",
+        }
+    }
+
+    /// Content-Type for an explicitly typed raw body. Multipart gets its own
+    /// from the client, with the boundary, and files from their extension.
+    pub fn content_type(self) -> Option<&'static str> {
+        match self {
+            BodyType::Json | BodyType::GraphQl => Some("application/json"),
+            BodyType::Xml => Some("application/xml"),
+            BodyType::Html => Some("text/html"),
+            BodyType::Text => Some("text/plain"),
+            BodyType::Form => Some("application/x-www-form-urlencoded"),
+            BodyType::Auto | BodyType::Multipart | BodyType::File => None,
+        }
+    }
+
+    /// Whether the body is edited as key-value fields.
+    pub fn is_form(self) -> bool {
+        matches!(self, BodyType::Form | BodyType::Multipart)
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum AuthType {
     #[default]
     None,
     Basic { username: String, password: String },
+    Digest { username: String, password: String },
     Bearer { token: String },
-    ApiKey { key: String, value: String },
+    ApiKey {
+        key: String,
+        value: String,
+        #[serde(default)]
+        location: KeyLocation,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuthConfig),
+    #[serde(rename = "aws_sigv4")]
+    AwsSigV4(AwsCredentials),
 }
 
 impl AuthType {
@@ -43,34 +138,265 @@ impl AuthType {
         match self {
             AuthType::None => "None",
             AuthType::Basic { .. } => "Basic",
+            AuthType::Digest { .. } => "Digest",
             AuthType::Bearer { .. } => "Bearer",
             AuthType::ApiKey { .. } => "API Key",
+            AuthType::OAuth2(_) => "OAuth 2.0",
+            AuthType::AwsSigV4(_) => "AWS SigV4",
         }
     }
 
     pub fn cycle_next(&self) -> AuthType {
         match self {
             AuthType::None => AuthType::Basic { username: String::new(), password: String::new() },
-            AuthType::Basic { .. } => AuthType::Bearer { token: String::new() },
-            AuthType::Bearer { .. } => AuthType::ApiKey { key: String::new(), value: String::new() },
-            AuthType::ApiKey { .. } => AuthType::None,
+            AuthType::Basic { .. } => AuthType::Digest { username: String::new(), password: String::new() },
+            AuthType::Digest { .. } => AuthType::Bearer { token: String::new() },
+            AuthType::Bearer { .. } => AuthType::api_key(),
+            AuthType::ApiKey { .. } => AuthType::OAuth2(OAuthConfig::default()),
+            AuthType::OAuth2(_) => AuthType::AwsSigV4(AwsCredentials::default()),
+            AuthType::AwsSigV4(_) => AuthType::None,
         }
     }
 
     pub fn cycle_prev(&self) -> AuthType {
         match self {
-            AuthType::None => AuthType::ApiKey { key: String::new(), value: String::new() },
+            AuthType::None => AuthType::AwsSigV4(AwsCredentials::default()),
             AuthType::Basic { .. } => AuthType::None,
-            AuthType::Bearer { .. } => AuthType::Basic { username: String::new(), password: String::new() },
+            AuthType::Digest { .. } => AuthType::Basic { username: String::new(), password: String::new() },
+            AuthType::Bearer { .. } => AuthType::Digest { username: String::new(), password: String::new() },
             AuthType::ApiKey { .. } => AuthType::Bearer { token: String::new() },
+            AuthType::OAuth2(_) => AuthType::api_key(),
+            AuthType::AwsSigV4(_) => AuthType::OAuth2(OAuthConfig::default()),
+        }
+    }
+
+    /// Rebuild the variant with every text field passed through `f`.
+    pub fn map_fields(&self, mut f: impl FnMut(&str) -> String) -> AuthType {
+        match self {
+            AuthType::None => AuthType::None,
+            AuthType::Basic { username, password } => AuthType::Basic {
+                username: f(username),
+                password: f(password),
+            },
+            AuthType::Digest { username, password } => AuthType::Digest {
+                username: f(username),
+                password: f(password),
+            },
+            AuthType::Bearer { token } => AuthType::Bearer { token: f(token) },
+            AuthType::ApiKey { key, value, location } => AuthType::ApiKey {
+                key: f(key),
+                value: f(value),
+                location: *location,
+            },
+            AuthType::OAuth2(config) => AuthType::OAuth2(OAuthConfig {
+                grant: config.grant,
+                token_url: f(&config.token_url),
+                auth_url: f(&config.auth_url),
+                client_id: f(&config.client_id),
+                client_secret: f(&config.client_secret),
+                scope: f(&config.scope),
+                username: f(&config.username),
+                password: f(&config.password),
+                refresh_token: f(&config.refresh_token),
+            }),
+            AuthType::AwsSigV4(aws) => AuthType::AwsSigV4(AwsCredentials {
+                access_key: f(&aws.access_key),
+                secret_key: f(&aws.secret_key),
+                session_token: f(&aws.session_token),
+                region: f(&aws.region),
+                service: f(&aws.service),
+            }),
+        }
+    }
+
+    fn api_key() -> AuthType {
+        AuthType::ApiKey { key: String::new(), value: String::new(), location: KeyLocation::default() }
+    }
+
+    /// The fields shown in the editor as (label, value), in order.
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            AuthType::None => vec![],
+            AuthType::Basic { username, password } | AuthType::Digest { username, password } => {
+                vec![("Username", username), ("Password", password)]
+            }
+            AuthType::Bearer { token } => vec![("Token", token)],
+            AuthType::ApiKey { key, value, location } => match location {
+                KeyLocation::Header => vec![("Header Name", key), ("Header Value", value)],
+                KeyLocation::Query => vec![("Param Name", key), ("Param Value", value)],
+                KeyLocation::Cookie => vec![("Cookie Name", key), ("Cookie Value", value)],
+            },
+            AuthType::OAuth2(config) => config.labels().iter().map(|&label| (label, config.field(label))).collect(),
+            AuthType::AwsSigV4(aws) => vec![
+                ("Access Key ID", &aws.access_key),
+                ("Secret Access Key", &aws.secret_key),
+                ("Session Token", &aws.session_token),
+                ("Region", &aws.region),
+                ("Service", &aws.service),
+            ],
         }
     }
 
-    pub fn has_two_fields(&self) -> bool {
-        matches!(self, AuthType::Basic { .. } | AuthType::ApiKey { .. })
+    /// Replace the editor's fields with `values`, in `fields` order.
+    pub fn set_fields(&mut self, values: Vec<String>) {
+        let mut values = values.into_iter();
+        let mut next = || values.next().unwrap_or_default();
+        match self {
+            AuthType::None => {}
+            AuthType::Basic { username, password } | AuthType::Digest { username, password } => {
+                (*username, *password) = (next(), next())
+            }
+            AuthType::Bearer { token } => *token = next(),
+            AuthType::ApiKey { key, value, .. } => (*key, *value) = (next(), next()),
+            AuthType::OAuth2(config) => {
+                for label in config.labels() {
+                    *config.field_mut(label) = next();
+                }
+            }
+            AuthType::AwsSigV4(aws) => {
+                for field in [&mut aws.access_key, &mut aws.secret_key, &mut aws.session_token, &mut aws.region, &mut aws.service] {
+                    *field = next();
+                }
+            }
+        }
+    }
+
+    /// Whether a field is masked outside the editor.
+    pub fn is_secret(label: &str) -> bool {
+        matches!(
+            label,
+            "Password" | "Token" | "Client Secret" | "Refresh Token" | "Secret Access Key" | "Session Token"
+        )
     }
 }
 
+/// Where an API key is sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyLocation {
+    #[default]
+    Header,
+    Query,
+    Cookie,
+}
+
+impl KeyLocation {
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyLocation::Header => "Header",
+            KeyLocation::Query => "Query param",
+            KeyLocation::Cookie => "Cookie",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            KeyLocation::Header => KeyLocation::Query,
+            KeyLocation::Query => KeyLocation::Cookie,
+            KeyLocation::Cookie => KeyLocation::Header,
+        }
+    }
+}
+
+/// How an OAuth 2.0 access token is obtained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grant {
+    #[default]
+    ClientCredentials,
+    Password,
+    RefreshToken,
+    /// With PKCE, through the browser and a loopback redirect.
+    AuthorizationCode,
+}
+
+impl Grant {
+    pub fn label(self) -> &'static str {
+        match self {
+            Grant::ClientCredentials => "Client credentials",
+            Grant::Password => "Password",
+            Grant::RefreshToken => "Refresh token",
+            Grant::AuthorizationCode => "Authorization code (PKCE)",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Grant::ClientCredentials => Grant::Password,
+            Grant::Password => Grant::RefreshToken,
+            Grant::RefreshToken => Grant::AuthorizationCode,
+            Grant::AuthorizationCode => Grant::ClientCredentials,
+        }
+    }
+}
+
+/// Where and how to get OAuth 2.0 access tokens. Fields the grant doesn't
+/// use are kept, so switching grants back and forth loses nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OAuthConfig {
+    pub grant: Grant,
+    pub token_url: String,
+    pub auth_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: String,
+    pub username: String,
+    pub password: String,
+    pub refresh_token: String,
+}
+
+impl OAuthConfig {
+    /// The fields the grant uses, in editor order.
+    fn labels(&self) -> &'static [&'static str] {
+        match self.grant {
+            Grant::ClientCredentials => &["Token URL", "Client ID", "Client Secret", "Scope"],
+            Grant::Password => &["Token URL", "Client ID", "Client Secret", "Username", "Password", "Scope"],
+            Grant::RefreshToken => &["Token URL", "Client ID", "Client Secret", "Refresh Token", "Scope"],
+            Grant::AuthorizationCode => &["Auth URL", "Token URL", "Client ID", "Client Secret", "Scope"],
+        }
+    }
+
+    fn field(&self, label: &str) -> &str {
+        match label {
+            "Token URL" => &self.token_url,
+            "Auth URL" => &self.auth_url,
+            "Client ID" => &self.client_id,
+            "Client Secret" => &self.client_secret,
+            "Username" => &self.username,
+            "Password" => &self.password,
+            "Refresh Token" => &self.refresh_token,
+            _ => &self.scope,
+        }
+    }
+
+    fn field_mut(&mut self, label: &str) -> &mut String {
+        match label {
+            "Token URL" => &mut self.token_url,
+            "Auth URL" => &mut self.auth_url,
+            "Client ID" => &mut self.client_id,
+            "Client Secret" => &mut self.client_secret,
+            "Username" => &mut self.username,
+            "Password" => &mut self.password,
+            "Refresh Token" => &mut self.refresh_token,
+            _ => &mut self.scope,
+        }
+    }
+}
+
+/// Credentials and scope for AWS Signature Version 4.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AwsCredentials {
+    pub access_key: String,
+    pub secret_key: String,
+    /// For temporary credentials; sent as `X-Amz-Security-Token`.
+    pub session_token: String,
+    pub region: String,
+    /// e.g. `execute-api` for API Gateway, `s3`.
+    pub service: String,
+}
+
 impl HttpMethod {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -81,6 +407,31 @@ impl HttpMethod {
             HttpMethod::Delete => "DEL",
             HttpMethod::Head => "HEAD",
             HttpMethod::Options => "OPT",
+            HttpMethod::Ws => "WS",
+        }
+    }
+
+    /// The full method name as sent on the wire.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Options => "OPTIONS",
+            other => other.as_str(),
+        }
+    }
+
+    /// Parse a full method name such as "DELETE" (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "GET" => Some(HttpMethod::Get),
+            "POST" => Some(HttpMethod::Post),
+            "PUT" => Some(HttpMethod::Put),
+            "PATCH" => Some(HttpMethod::Patch),
+            "DELETE" => Some(HttpMethod::Delete),
+            "HEAD" => Some(HttpMethod::Head),
+            "OPTIONS" => Some(HttpMethod::Options),
+            "WS" => Some(HttpMethod::Ws),
+            _ => None,
         }
     }
 
@@ -92,47 +443,87 @@ impl HttpMethod {
             HttpMethod::Patch => HttpMethod::Delete,
             HttpMethod::Delete => HttpMethod::Head,
             HttpMethod::Head => HttpMethod::Options,
-            HttpMethod::Options => HttpMethod::Get,
+            HttpMethod::Options => HttpMethod::Ws,
+            HttpMethod::Ws => HttpMethod::Get,
         }
     }
 
     pub fn prev(self) -> Self {
         match self {
-            HttpMethod::Get => HttpMethod::Options,
+            HttpMethod::Get => HttpMethod::Ws,
             HttpMethod::Post => HttpMethod::Get,
             HttpMethod::Put => HttpMethod::Post,
             HttpMethod::Patch => HttpMethod::Put,
             HttpMethod::Delete => HttpMethod::Patch,
             HttpMethod::Head => HttpMethod::Delete,
             HttpMethod::Options => HttpMethod::Head,
+            HttpMethod::Ws => HttpMethod::Options,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Stable identity of a saved request. The default value means "not yet saved".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RequestId(pub u64);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Request {
+    #[serde(default)]
+    pub id: RequestId,
+    #[serde(default)]
+    pub name: String,
     pub method: HttpMethod,
     pub url: String,
     pub params: Vec<KeyValue>,
     pub headers: Vec<KeyValue>,
     pub body: String,
+    #[serde(default)]
+    pub body_type: BodyType,
+    /// Fields of a form or multipart body.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<KeyValue>,
+    /// Path of the file sent as a file body.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body_file: String,
+    /// JSON variables of a GraphQL body, whose query is `body`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub variables: String,
     pub auth: AuthType,
+    /// jq filter applied to this request's responses.
+    #[serde(default)]
+    pub response_filter: String,
+    #[serde(default, skip_serializing_if = "SettingsOverride::is_empty")]
+    pub settings: SettingsOverride,
     pub created_at: SystemTime,
 }
 
 impl Request {
     pub fn new(method: HttpMethod, url: impl Into<String>) -> Self {
         Self {
+            id: RequestId::default(),
+            name: String::new(),
             method,
             url: url.into(),
             params: vec![],
             headers: vec![],
             body: String::new(),
+            body_type: BodyType::Auto,
+            form: vec![],
+            body_file: String::new(),
+            variables: String::new(),
             auth: AuthType::None,
+            response_filter: String::new(),
+            settings: SettingsOverride::default(),
             created_at: SystemTime::now(),
         }
     }
 
+    /// The request's name, falling back to its URL when unnamed.
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() { &self.url } else { &self.name }
+    }
+
     pub fn relative_time(&self) -> String {
         let elapsed = self.created_at.elapsed().unwrap_or_default();
         let secs = elapsed.as_secs();
@@ -1,47 +1,152 @@
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::utils::format_json_if_valid;
+use super::cookie::Cookie;
+use super::stream::EventLog;
+use crate::utils::{format_json_if_valid, format_size};
 
 #[derive(Debug, Clone)]
 pub struct Response {
     pub status: u16,
     pub status_text: String,
-    #[allow(dead_code)]
+    /// Final URL, after any redirects.
+    pub url: String,
+    pub version: String,
+    pub remote_addr: Option<String>,
+    /// In received order; repeated headers appear once per value.
     pub headers: Vec<(String, String)>,
+    /// The body as text; binary bodies are decoded lossily.
     pub body: String,
+    /// The body as received, kept only when it isn't valid UTF-8 (otherwise
+    /// `body` holds exactly these bytes).
+    pub raw_body: Option<Vec<u8>>,
+    /// The full body, when it was too large to keep in memory; `body` then
+    /// holds only its start.
+    pub spill: Option<Arc<SpillFile>>,
+    /// Set for `text/event-stream` responses, whose body is shown as a log
+    /// of the events received.
+    pub events: Option<EventLog>,
+    /// Time until the status line and headers arrived.
+    pub time_to_headers: Duration,
     pub elapsed: Duration,
     pub size_bytes: usize,
 }
 
+/// A temporary file holding a response body; removed when dropped.
+#[derive(Debug)]
+pub struct SpillFile {
+    pub path: PathBuf,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
 impl Response {
     /// Format elapsed time for display (e.g., "123ms", "1.2s")
     pub fn elapsed_display(&self) -> String {
-        let ms = self.elapsed.as_millis();
-        if ms < 1000 {
-            format!("{}ms", ms)
-        } else {
-            format!("{:.1}s", self.elapsed.as_secs_f64())
-        }
+        format_duration(self.elapsed)
     }
 
     /// Format body size for display (e.g., "1.2 KB", "3.4 MB")
     pub fn size_display(&self) -> String {
-        let bytes = self.size_bytes;
-        if bytes < 1024 {
-            format!("{} B", bytes)
-        } else if bytes < 1024 * 1024 {
-            format!("{:.1} KB", bytes as f64 / 1024.0)
-        } else {
-            format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
-        }
+        format_size(self.size_bytes as u64)
     }
 
     pub fn formatted_body(&self) -> String {
         format_json_if_valid(&self.body)
     }
 
-    pub fn line_count(&self) -> usize {
-        self.formatted_body().lines().count()
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body exactly as received, or its start if it was spilled to disk.
+    pub fn bytes(&self) -> &[u8] {
+        self.raw_body.as_deref().unwrap_or(self.body.as_bytes())
+    }
+
+    /// Whether the body is better shown as a hex dump than as text.
+    pub fn is_binary(&self) -> bool {
+        let mime = self
+            .content_type()
+            .and_then(|ct| ct.split(';').next())
+            .map(|ct| ct.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let textual = mime.starts_with("text/")
+            || ["json", "xml", "javascript", "yaml", "x-www-form-urlencoded", "graphql"]
+                .iter()
+                .any(|kind| mime.contains(kind));
+        let binary = ["image/", "audio/", "video/", "font/"].iter().any(|p| mime.starts_with(p))
+            || ["octet-stream", "pdf", "zip", "gzip", "protobuf", "grpc", "msgpack", "wasm"]
+                .iter()
+                .any(|kind| mime.contains(kind));
+
+        match (textual, binary) {
+            (true, _) => false,
+            (_, true) => true,
+            _ => self.raw_body.is_some() || self.body.contains('\0'),
+        }
+    }
+
+    /// A file name to save the body as: from `Content-Disposition`, else the
+    /// last segment of the URL.
+    pub fn suggested_file_name(&self) -> String {
+        let from_disposition = self
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-disposition"))
+            .and_then(|(_, value)| {
+                value.split(';').find_map(|part| {
+                    let (key, name) = part.trim().split_once('=')?;
+                    key.eq_ignore_ascii_case("filename").then(|| name.trim_matches('"').to_string())
+                })
+            });
+        let from_url = || {
+            let path = self.url.split(['?', '#']).next().unwrap_or_default();
+            let last = path.rsplit('/').next().unwrap_or_default();
+            (!last.is_empty() && !path.ends_with("//")).then(|| last.to_string())
+        };
+
+        from_disposition
+            .filter(|name| !name.is_empty() && !name.contains('/'))
+            .or_else(from_url)
+            .unwrap_or_else(|| "response.bin".to_string())
+    }
+
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .filter_map(|(_, value)| Cookie::parse_set_cookie(value))
+            .collect()
+    }
+}
+
+/// Split a received body into its text and, if it isn't UTF-8, the raw bytes.
+pub fn decode_body(bytes: Vec<u8>) -> (String, Option<Vec<u8>>) {
+    match String::from_utf8(bytes) {
+        Ok(text) => (text, None),
+        Err(e) => {
+            let bytes = e.into_bytes();
+            (String::from_utf8_lossy(&bytes).into_owned(), Some(bytes))
+        }
+    }
+}
+
+/// Format a duration for display (e.g., "123ms", "1.2s")
+pub fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_millis();
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
     }
 }
 
@@ -50,6 +155,7 @@ pub enum RequestState {
     #[default]
     Idle,
     Loading,
-    Success(Response),
+    Success(Box<Response>),
     Error(String),
+    Cancelled,
 }
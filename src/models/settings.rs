@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+pub const DEFAULT_USER_AGENT: &str = "Courier/0.1.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Setting {
+    Timeout,
+    FollowRedirects,
+    MaxRedirects,
+    VerifyTls,
+    CaBundle,
+    Proxy,
+    NoProxy,
+    UserAgent,
+    Cookies,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 9] = [
+        Setting::Timeout,
+        Setting::FollowRedirects,
+        Setting::MaxRedirects,
+        Setting::VerifyTls,
+        Setting::CaBundle,
+        Setting::Proxy,
+        Setting::NoProxy,
+        Setting::UserAgent,
+        Setting::Cookies,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::Timeout => "Timeout",
+            Setting::FollowRedirects => "Follow redirects",
+            Setting::MaxRedirects => "Max redirects",
+            Setting::VerifyTls => "Verify TLS",
+            Setting::CaBundle => "CA bundle",
+            Setting::Proxy => "Proxy",
+            Setting::NoProxy => "No proxy",
+            Setting::UserAgent => "User agent",
+            Setting::Cookies => "Send cookies",
+        }
+    }
+
+    pub fn is_toggle(self) -> bool {
+        matches!(self, Setting::FollowRedirects | Setting::VerifyTls | Setting::Cookies)
+    }
+}
+
+/// How a request is sent: the client it goes through, how long it may take
+/// and how far it follows redirects.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientSettings {
+    /// Whole-request timeout, or until the headers for an event stream;
+    /// 0 disables it.
+    pub timeout_secs: u64,
+    pub follow_redirects: bool,
+    pub max_redirects: usize,
+    /// Off accepts self-signed and otherwise invalid certificates.
+    pub verify_tls: bool,
+    /// Path to extra PEM root certificates.
+    pub ca_bundle: String,
+    /// `http://`, `https://` or `socks5://` proxy URL.
+    pub proxy: String,
+    /// Comma-separated hosts that bypass the proxy.
+    pub no_proxy: String,
+    pub user_agent: String,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            follow_redirects: true,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            verify_tls: true,
+            ca_bundle: String::new(),
+            proxy: String::new(),
+            no_proxy: String::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+}
+
+/// Everything the settings overlay edits: the client settings, and those
+/// applied to each request on top of its client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub client: ClientSettings,
+    /// Attach matching cookies from the jar; responses still fill it.
+    pub send_cookies: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { client: ClientSettings::default(), send_cookies: true }
+    }
+}
+
+impl Settings {
+    pub fn display(&self, setting: Setting) -> String {
+        let text = |s: &str| if s.is_empty() { "none".to_string() } else { s.to_string() };
+        let flag = |b: bool| if b { "on" } else { "off" }.to_string();
+        match setting {
+            Setting::Timeout if self.client.timeout_secs == 0 => "none".to_string(),
+            Setting::Timeout => format!("{}s", self.client.timeout_secs),
+            Setting::FollowRedirects => flag(self.client.follow_redirects),
+            Setting::MaxRedirects => self.client.max_redirects.to_string(),
+            Setting::VerifyTls => flag(self.client.verify_tls),
+            Setting::CaBundle => text(&self.client.ca_bundle),
+            Setting::Proxy => text(&self.client.proxy),
+            Setting::NoProxy => text(&self.client.no_proxy),
+            Setting::UserAgent => text(&self.client.user_agent),
+            Setting::Cookies => flag(self.send_cookies),
+        }
+    }
+
+    /// The value as typed into the editor.
+    pub fn raw(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Timeout => self.client.timeout_secs.to_string(),
+            Setting::MaxRedirects => self.client.max_redirects.to_string(),
+            Setting::CaBundle => self.client.ca_bundle.clone(),
+            Setting::Proxy => self.client.proxy.clone(),
+            Setting::NoProxy => self.client.no_proxy.clone(),
+            Setting::UserAgent => self.client.user_agent.clone(),
+            Setting::FollowRedirects | Setting::VerifyTls | Setting::Cookies => self.display(setting),
+        }
+    }
+
+    pub fn set(&mut self, setting: Setting, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match setting {
+            Setting::Timeout => self.client.timeout_secs = parse_secs(value)?,
+            Setting::FollowRedirects => self.client.follow_redirects = parse_flag(value)?,
+            Setting::MaxRedirects => {
+                self.client.max_redirects = value.parse().map_err(|_| format!("'{}' is not a number", value))?
+            }
+            Setting::VerifyTls => self.client.verify_tls = parse_flag(value)?,
+            Setting::CaBundle => self.client.ca_bundle = value.to_string(),
+            Setting::Proxy => self.client.proxy = value.to_string(),
+            Setting::NoProxy => self.client.no_proxy = value.to_string(),
+            Setting::UserAgent => self.client.user_agent = value.to_string(),
+            Setting::Cookies => self.send_cookies = parse_flag(value)?,
+        }
+        Ok(())
+    }
+
+    pub fn toggle(&mut self, setting: Setting) {
+        match setting {
+            Setting::FollowRedirects => self.client.follow_redirects = !self.client.follow_redirects,
+            Setting::VerifyTls => self.client.verify_tls = !self.client.verify_tls,
+            Setting::Cookies => self.send_cookies = !self.send_cookies,
+            _ => {}
+        }
+    }
+
+    /// These settings with a request's overrides applied.
+    pub fn resolve(&self, overrides: &SettingsOverride) -> Settings {
+        let mut settings = self.clone();
+        for (setting, value) in &overrides.values {
+            // Stored overrides were validated when set
+            let _ = settings.set(*setting, value);
+        }
+        settings
+    }
+}
+
+/// Per-request overrides of the global settings, kept as the text that was
+/// entered so an unset value keeps following the global one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SettingsOverride {
+    values: BTreeMap<Setting, String>,
+}
+
+impl SettingsOverride {
+    pub fn get(&self, setting: Setting) -> Option<&str> {
+        self.values.get(&setting).map(String::as_str)
+    }
+
+    /// Override `setting`, validating `value` against `base`.
+    pub fn set(&mut self, base: &Settings, setting: Setting, value: &str) -> Result<(), String> {
+        let mut check = base.clone();
+        check.set(setting, value)?;
+        self.values.insert(setting, check.raw(setting));
+        Ok(())
+    }
+
+    pub fn clear(&mut self, setting: Setting) {
+        self.values.remove(&setting);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+fn parse_secs(value: &str) -> Result<u64, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "none" | "off" => Ok(0),
+        v => v.trim_end_matches('s').trim().parse().map_err(|_| format!("'{}' is not a number of seconds", value)),
+    }
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("'{}' is not on/off", value)),
+    }
+}
@@ -0,0 +1,162 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// One event from a `text/event-stream` response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event` field; `message` when the server didn't name it.
+    pub event: String,
+    pub id: Option<String>,
+    /// The `data` lines, joined with newlines.
+    pub data: String,
+    /// Reconnection delay the server asked for, in milliseconds.
+    pub retry: Option<u64>,
+    /// When it arrived, from the start of the request.
+    pub at: Duration,
+}
+
+/// A frame sent or received on a WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsFrame {
+    pub sent: bool,
+    pub kind: FrameKind,
+    /// Text payload, a close reason, or a description of binary data.
+    pub data: String,
+    /// When it was sent or received, from the start of the request.
+    pub at: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Text,
+    Binary,
+    Ping,
+    Pong,
+    /// With its status code, if it had one.
+    Close(Option<u16>),
+}
+
+impl FrameKind {
+    pub fn label(self) -> String {
+        match self {
+            FrameKind::Text => "text".to_string(),
+            FrameKind::Binary => "binary".to_string(),
+            FrameKind::Ping => "ping".to_string(),
+            FrameKind::Pong => "pong".to_string(),
+            FrameKind::Close(Some(code)) => format!("close {}", code),
+            FrameKind::Close(None) => "close".to_string(),
+        }
+    }
+}
+
+/// An entry in the log of a streamed response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    Sse(SseEvent),
+    Ws(WsFrame),
+}
+
+/// The events received on a stream or socket so far.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    pub events: Vec<StreamEvent>,
+    /// Why the stream ended; `None` while it is open.
+    pub ended: Option<String>,
+}
+
+/// Incremental `text/event-stream` parser, fed bytes as they arrive.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    /// The last byte was `\r`, so a following `\n` ends no further line.
+    after_cr: bool,
+    started: bool,
+    /// The event being built from the fields seen since the last blank line.
+    pending: Option<SseEvent>,
+}
+
+impl SseParser {
+    /// Parse `bytes`, returning the events they complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = vec![];
+        for &byte in bytes {
+            if std::mem::take(&mut self.after_cr) && byte == b'\n' {
+                continue;
+            }
+            match byte {
+                b'\r' | b'\n' => {
+                    self.after_cr = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    events.extend(self.process_line(&line));
+                }
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        let mut line = String::from_utf8_lossy(line);
+        if !std::mem::replace(&mut self.started, true)
+            && let Some(rest) = line.strip_prefix('\u{feff}')
+        {
+            line = Cow::Owned(rest.to_string());
+        }
+
+        // A blank line dispatches the event, unless it has no data
+        if line.is_empty() {
+            let mut event = self.pending.take().filter(|event| !event.data.is_empty())?;
+            if event.data.ends_with('\n') {
+                event.data.pop();
+            }
+            if event.event.is_empty() {
+                event.event = "message".to_string();
+            }
+            return Some(event);
+        }
+        // Comments, often sent as keep-alives
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+        match field {
+            "event" => self.event().event = value.to_string(),
+            "data" => {
+                let event = self.event();
+                event.data.push_str(value);
+                event.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.event().id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.event().retry = value.parse().ok();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn event(&mut self) -> &mut SseEvent {
+        self.pending.get_or_insert_with(SseEvent::default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_without_data_is_dropped() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b"event: ping\n\n").is_empty());
+        assert!(parser.feed(b"id: 1\n\n").is_empty());
+
+        let events = parser.feed(b"event: tick\ndata\n\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "tick");
+        assert_eq!(events[0].data, "");
+    }
+}
@@ -0,0 +1,80 @@
+use regex::{Regex, RegexBuilder};
+
+/// A match as a byte range within one line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A regex search over a block of lines, with a current hit for `n`/`N`.
+pub struct Search {
+    pub pattern: String,
+    pub backward: bool,
+    regex: Regex,
+    matches: Vec<SearchMatch>,
+    current: Option<usize>,
+}
+
+impl Search {
+    /// Case-insensitive unless the pattern contains an uppercase letter.
+    pub fn new(pattern: &str, backward: bool) -> Result<Self, String> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!pattern.chars().any(char::is_uppercase))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            backward,
+            regex,
+            matches: vec![],
+            current: None,
+        })
+    }
+
+    /// Find every match in `lines`, making the first hit from `from_line`
+    /// (in the search direction, wrapping) current.
+    pub fn run<'a>(&mut self, lines: impl Iterator<Item = &'a str>, from_line: usize) {
+        self.matches = lines
+            .enumerate()
+            .flat_map(|(line, text)| {
+                self.regex
+                    .find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(move |m| SearchMatch { line, start: m.start(), end: m.end() })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        self.current = if self.backward {
+            self.matches.iter().rposition(|m| m.line < from_line)
+        } else {
+            self.matches.iter().position(|m| m.line >= from_line)
+        }
+        .or_else(|| (!self.matches.is_empty()).then(|| if self.backward { self.matches.len() - 1 } else { 0 }));
+    }
+
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    pub fn current(&self) -> Option<SearchMatch> {
+        self.current.map(|i| self.matches[i])
+    }
+
+    /// Index of the current hit, counted from 1, for display.
+    pub fn position(&self) -> Option<usize> {
+        self.current.map(|i| i + 1)
+    }
+
+    /// Step to the next hit in the search direction, or against it with `reverse`.
+    pub fn step(&mut self, reverse: bool) -> Option<SearchMatch> {
+        let len = self.matches.len();
+        let current = self.current?;
+        let forward = self.backward == reverse;
+        self.current = Some(if forward { (current + 1) % len } else { (current + len - 1) % len });
+        self.current()
+    }
+}
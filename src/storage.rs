@@ -0,0 +1,158 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::{Collection, CookieJar, Environment, Settings};
+
+/// Version of the on-disk format. Bump this and add a step to `migrate`
+/// whenever the stored layout changes.
+const SCHEMA_VERSION: u64 = 2;
+
+const DATA_FILE: &str = "requests.json";
+
+/// Everything persisted between sessions.
+#[derive(Debug, Default, Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub collection: Collection,
+    #[serde(default)]
+    pub environments: Vec<Environment>,
+    #[serde(default)]
+    pub active_environment: Option<usize>,
+    #[serde(default)]
+    pub settings: Settings,
+    /// Cookies received with no environment active.
+    #[serde(default)]
+    pub cookies: CookieJar,
+}
+
+/// Borrowed form of `Workspace`, so saving doesn't clone the app state.
+#[derive(Serialize)]
+pub struct WorkspaceRef<'a> {
+    pub collection: &'a Collection,
+    pub environments: &'a [Environment],
+    pub active_environment: Option<usize>,
+    pub settings: &'a Settings,
+    pub cookies: &'a CookieJar,
+}
+
+#[derive(Serialize)]
+struct StoreRef<'a> {
+    version: u64,
+    #[serde(flatten)]
+    workspace: WorkspaceRef<'a>,
+}
+
+pub struct Storage {
+    path: PathBuf,
+}
+
+impl Storage {
+    /// Storage rooted in the platform data dir (`$XDG_DATA_HOME/courier` on Linux).
+    pub fn open_default() -> Option<Self> {
+        dirs::data_dir().map(|dir| Self::new(dir.join("courier")))
+    }
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { path: dir.into().join(DATA_FILE) }
+    }
+
+    pub fn load(&self) -> Result<Workspace> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Workspace::default()),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", self.path.display())),
+        };
+        // Tighten files saved before they were written privately; a read-only
+        // file still loads
+        let _ = owner_only(&self.path);
+
+        let value: Value = serde_json::from_str(&text)
+            .with_context(|| format!("parsing {}", self.path.display()))?;
+        let mut workspace: Workspace = serde_json::from_value(migrate(value)?)?;
+        workspace.collection.assign_ids();
+        Ok(workspace)
+    }
+
+    pub fn save(&self, workspace: WorkspaceRef) -> Result<()> {
+        let store = StoreRef { version: SCHEMA_VERSION, workspace };
+        let json = serde_json::to_string_pretty(&store)?;
+        write_atomic(&self.path, json.as_bytes())
+            .with_context(|| format!("writing {}", self.path.display()))
+    }
+}
+
+/// Bring a stored document up to `SCHEMA_VERSION`.
+fn migrate(mut value: Value) -> Result<Value> {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SCHEMA_VERSION {
+        bail!(
+            "data file version {} is newer than supported version {}",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+
+    // v1: a flat `requests` list. v2 nests them as nodes of a collection tree.
+    if version < 2 {
+        let requests = match value.get_mut("requests").map(Value::take) {
+            Some(Value::Array(requests)) => requests,
+            _ => vec![],
+        };
+        let items: Vec<Value> = requests
+            .into_iter()
+            .map(|mut req| {
+                if let Value::Object(fields) = &mut req {
+                    fields.insert("kind".to_string(), Value::from("request"));
+                }
+                req
+            })
+            .collect();
+        value = serde_json::json!({ "collection": { "items": items } });
+    }
+
+    Ok(value)
+}
+
+/// Write to a sibling temp file, flush it to disk and rename it over the
+/// target, so a crash mid-write never leaves a truncated data file behind.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path.parent().context("data file has no parent directory")?;
+    fs::create_dir_all(dir)?;
+
+    let tmp = path.with_extension("json.tmp");
+    {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp)?;
+        // A temp file left over from a crash keeps the mode it was made with
+        owner_only(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+
+    // Persist the rename itself; not all platforms allow opening directories.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Let only the owner read `path`: the data file holds passwords, tokens,
+/// secrets and session cookies.
+fn owner_only(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeSet;
+
+use crate::models::{AuthType, KeyValue};
+
+/// A piece of a templated string: literal text or a `{{name}}` reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Var { name: &'a str, raw: &'a str },
+}
+
+pub fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut out = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        let end = start + 2 + len + 2;
+
+        if start > 0 {
+            out.push(Segment::Text(&rest[..start]));
+        }
+        out.push(Segment::Var {
+            name: rest[start + 2..end - 2].trim(),
+            raw: &rest[start..end],
+        });
+        rest = &rest[end..];
+    }
+
+    if !rest.is_empty() {
+        out.push(Segment::Text(rest));
+    }
+    out
+}
+
+pub fn lookup<'a>(vars: &'a [KeyValue], name: &str) -> Option<&'a str> {
+    vars.iter()
+        .rev()
+        .find(|v| v.enabled && v.key == name)
+        .map(|v| v.value.as_str())
+}
+
+/// Expands `{{var}}` references, remembering every name it couldn't resolve
+/// so the caller can refuse to send a half-templated request.
+pub struct Expander<'a> {
+    vars: &'a [KeyValue],
+    missing: BTreeSet<String>,
+}
+
+impl<'a> Expander<'a> {
+    pub fn new(vars: &'a [KeyValue]) -> Self {
+        Self { vars, missing: BTreeSet::new() }
+    }
+
+    pub fn text(&mut self, text: &str) -> String {
+        segments(text)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text,
+                Segment::Var { name, raw } => lookup(self.vars, name).unwrap_or_else(|| {
+                    self.missing.insert(name.to_string());
+                    raw
+                }),
+            })
+            .collect()
+    }
+
+    /// Expand enabled items only; disabled ones are never sent.
+    pub fn key_values(&mut self, items: &[KeyValue]) -> Vec<KeyValue> {
+        items
+            .iter()
+            .map(|item| {
+                if !item.enabled {
+                    return item.clone();
+                }
+                KeyValue {
+                    enabled: true,
+                    key: self.text(&item.key),
+                    value: self.text(&item.value),
+                }
+            })
+            .collect()
+    }
+
+    pub fn auth(&mut self, auth: &AuthType) -> AuthType {
+        auth.map_fields(|field| self.text(field))
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        if self.missing.is_empty() {
+            return Ok(());
+        }
+        let names: Vec<_> = self.missing.into_iter().collect();
+        Err(format!("Unresolved variables: {}", names.join(", ")))
+    }
+}
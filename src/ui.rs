@@ -1,14 +1,28 @@
+use std::time::{Duration, Instant};
+
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, HighlightSpacing, List, ListItem, Paragraph, Tabs},
+    widgets::{Block, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph, Tabs, Wrap},
 };
 
-use crate::app::{App, AuthField, EditFocus, KvField, KvEditor, Panel, RequestTab};
-use crate::models::{AuthType, HttpMethod, KeyValue, Request, RequestState};
-use crate::utils::{format_json_if_valid, textarea_value};
+use crate::app::{
+    App, EditFocus, ExportView, KvField, KvEditor, Panel, Prompt, PromptKind, RequestTab,
+    ResponseTab, SettingsScope,
+};
+use crate::binary;
+use crate::codegen::Language;
+use crate::files;
+use crate::highlight;
+use crate::json_tree::{JsonTree, RowKind};
+use crate::models::{
+    AuthType, BodyType, Folder, HttpMethod, KeyValue, Node, Request, RequestState, Response, Setting,
+    format_duration,
+};
+use crate::template::{self, Segment};
+use crate::utils::{format_size, textarea_value};
 
 pub mod theme {
     use ratatui::style::Color;
@@ -29,12 +43,25 @@ pub mod theme {
     pub const METHOD_DELETE: Color = Color::Rgb(251, 113, 133);
     pub const METHOD_HEAD: Color = Color::Rgb(94, 234, 212);
     pub const METHOD_OPTIONS: Color = Color::Rgb(156, 163, 175);
+    pub const METHOD_WS: Color = Color::Rgb(244, 114, 182);
 
     pub const STATUS_SUCCESS: Color = Color::Rgb(52, 211, 153);
     pub const STATUS_REDIRECT: Color = Color::Rgb(96, 165, 250);
     pub const STATUS_CLIENT_ERROR: Color = Color::Rgb(251, 191, 36);
     pub const STATUS_SERVER_ERROR: Color = Color::Rgb(251, 113, 133);
     pub const STATUS_LOADING: Color = Color::Rgb(139, 92, 246);
+
+    pub const SYNTAX_KEY: Color = Color::Rgb(96, 165, 250);
+    pub const SYNTAX_STRING: Color = Color::Rgb(52, 211, 153);
+    pub const SYNTAX_NUMBER: Color = Color::Rgb(251, 191, 36);
+    pub const SYNTAX_BOOL: Color = Color::Rgb(192, 132, 252);
+    pub const SYNTAX_NULL: Color = Color::Rgb(251, 113, 133);
+    pub const SYNTAX_PUNCT: Color = Color::Rgb(100, 116, 139);
+    pub const SYNTAX_TAG: Color = Color::Rgb(96, 165, 250);
+    pub const SYNTAX_ATTR: Color = Color::Rgb(251, 191, 36);
+
+    pub const SEARCH_MATCH: Color = Color::Rgb(75, 60, 20);
+    pub const SEARCH_CURRENT: Color = Color::Rgb(180, 120, 20);
 }
 
 pub fn render(frame: &mut Frame, app: &mut App) {
@@ -59,30 +86,100 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     render_response(frame, app, main[2]);
     render_status_bar(frame, app, outer[1]);
 
+    if app.show_environments {
+        render_environments_overlay(frame, app, area);
+    }
+
+    if app.show_settings {
+        render_settings_overlay(frame, app, area);
+    }
+
+    if app.show_cookies {
+        render_cookies_overlay(frame, app, area);
+    }
+
+    if let Some(export) = &app.export {
+        render_export_overlay(frame, export, area);
+    }
+
+    if let Some(prompt) = &app.prompt {
+        if prompt.kind.is_search() {
+            render_search_prompt(frame, prompt, outer[1]);
+        } else {
+            render_prompt(frame, prompt, area);
+        }
+    }
+
     if app.show_help {
         render_help_overlay(frame, app, area);
     }
 }
 
-fn create_request_list_item<'a>(req: &Request, max_url_len: usize) -> ListItem<'a> {
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+fn spinner_frame(since: Instant) -> char {
+    SPINNER[(since.elapsed().as_millis() / 80) as usize % SPINNER.len()]
+}
+
+fn create_request_list_item<'a>(
+    req: &Request,
+    depth: usize,
+    moving: bool,
+    loading_since: Option<Instant>,
+    max_url_len: usize,
+) -> ListItem<'a> {
     let placeholder = "https://api.example.com";
+    let label = req.label();
+    let max_url_len = max_url_len.saturating_sub(depth * 2);
 
-    let (url_text, url_color) = if req.url.is_empty() {
+    let (url_text, url_color) = if label.is_empty() {
         (placeholder.to_string(), theme::TEXT_DIM)
-    } else if req.url.len() > max_url_len {
-        (format!("{}...", &req.url[..max_url_len.saturating_sub(3)]), theme::TEXT)
+    } else if label.chars().count() > max_url_len {
+        let truncated: String = label.chars().take(max_url_len.saturating_sub(3)).collect();
+        (format!("{}...", truncated), theme::TEXT)
     } else {
-        (req.url.clone(), theme::TEXT)
+        (label.to_string(), theme::TEXT)
     };
 
+    let mut url_style = Style::default().fg(url_color);
+    if moving {
+        url_style = url_style.fg(theme::METHOD_POST).add_modifier(Modifier::ITALIC);
+    }
+
     let line = Line::from(vec![
+        Span::raw("  ".repeat(depth)),
         Span::styled(
             format!("{:6}", req.method.as_str()),
             Style::default().fg(method_color(req.method)),
         ),
-        Span::styled(url_text, Style::default().fg(url_color)),
+        Span::styled(url_text, url_style),
+        match loading_since {
+            Some(since) => Span::styled(
+                format!(" {:>4}", spinner_frame(since)),
+                Style::default().fg(theme::STATUS_LOADING),
+            ),
+            None => Span::styled(
+                format!(" {:>4}", req.relative_time()),
+                Style::default().fg(theme::TEXT_DIM),
+            ),
+        },
+    ]);
+
+    ListItem::new(line)
+}
+
+fn create_folder_list_item<'a>(folder: &Folder, depth: usize) -> ListItem<'a> {
+    let icon = if folder.expanded { "▾ " } else { "▸ " };
+
+    let line = Line::from(vec![
+        Span::raw("  ".repeat(depth)),
+        Span::styled(icon, Style::default().fg(theme::TEXT_DIM)),
+        Span::styled(
+            folder.name.clone(),
+            Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD),
+        ),
         Span::styled(
-            format!(" {:>4}", req.relative_time()),
+            format!(" ({})", folder.items.len()),
             Style::default().fg(theme::TEXT_DIM),
         ),
     ]);
@@ -95,7 +192,7 @@ fn render_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
     let border_color = if focused { theme::BORDER_FOCUSED } else { theme::BORDER };
 
     let block = Block::default()
-        .title(format!(" Requests ({}) ", app.requests.len()))
+        .title(format!(" Requests ({}) ", app.collection.request_count()))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(border_color))
         .style(Style::default().bg(theme::BG));
@@ -103,7 +200,7 @@ fn render_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    if app.requests.is_empty() {
+    if app.collection.is_empty() {
         let hint = Paragraph::new(Text::from(vec![
             Line::from(""),
             Line::from(Span::styled("No requests", Style::default().fg(theme::TEXT_DIM))),
@@ -118,9 +215,17 @@ fn render_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
 
     let max_url_len = inner.width.saturating_sub(14) as usize;
 
-    let items: Vec<ListItem> = app.requests
+    let items: Vec<ListItem> = app
+        .sidebar_rows()
         .iter()
-        .map(|req| create_request_list_item(req, max_url_len))
+        .filter_map(|row| match app.collection.node(&row.path)? {
+            Node::Folder(folder) => Some(create_folder_list_item(folder, row.depth)),
+            Node::Request(req) => {
+                let moving = app.moving_request == Some(req.id);
+                let loading_since = app.loading_since(req.id);
+                Some(create_request_list_item(req, row.depth, moving, loading_since, max_url_len))
+            }
+        })
         .collect();
 
     let list = List::new(items)
@@ -143,17 +248,19 @@ fn render_request_editor(frame: &mut Frame, app: &App, area: Rect) {
 
     let right_title: Line = match app.edit_focus {
         EditFocus::Url => Line::from(Span::styled(" URL ", Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))),
+        EditFocus::KeyValue if app.show_environments => Line::from(""),
         EditFocus::KeyValue => {
             let label = match app.active_tab {
                 RequestTab::Params => "PARAMS",
                 RequestTab::Headers => "HEADERS",
-                RequestTab::Body | RequestTab::Auth => "BODY",
+                RequestTab::Body => "FORM",
+                RequestTab::Auth => "BODY",
             };
             Line::from(Span::styled(format!(" {} ", label), Style::default().fg(theme::METHOD_POST).add_modifier(Modifier::BOLD)))
         },
         EditFocus::Body => Line::from(Span::styled(" BODY ", Style::default().fg(theme::METHOD_PUT).add_modifier(Modifier::BOLD))),
         EditFocus::Auth => Line::from(Span::styled(" AUTH ", Style::default().fg(theme::METHOD_DELETE).add_modifier(Modifier::BOLD))),
-        EditFocus::None => Line::from(""),
+        EditFocus::None | EditFocus::Prompt => Line::from(""),
     };
 
     let block = Block::default()
@@ -194,14 +301,36 @@ fn render_url_bar(frame: &mut Frame, app: &App, area: Rect) {
     } else {
         let placeholder = "https://api.example.com";
         let url = app.url();
-        let url_text = if url.is_empty() { placeholder } else { url };
-        let url_color = if url.is_empty() { theme::TEXT_DIM } else { theme::TEXT };
-        let url_para = Paragraph::new(Span::styled(url_text, Style::default().fg(url_color)))
-            .style(Style::default().bg(theme::BG));
+        let line = if url.is_empty() {
+            Line::from(Span::styled(placeholder, Style::default().fg(theme::TEXT_DIM)))
+        } else {
+            templated_line(url, app.active_variables())
+        };
+        let url_para = Paragraph::new(line).style(Style::default().bg(theme::BG));
         frame.render_widget(url_para, chunks[1]);
     }
 }
 
+/// Color `{{var}}` references: accent when the active environment defines
+/// them, error when they would be left unresolved.
+fn templated_line<'a>(text: &'a str, vars: &[KeyValue]) -> Line<'a> {
+    let spans: Vec<Span> = template::segments(text)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => Span::styled(text, Style::default().fg(theme::TEXT)),
+            Segment::Var { name, raw } => {
+                let style = if template::lookup(vars, name).is_some() {
+                    Style::default().fg(theme::ACCENT)
+                } else {
+                    Style::default().fg(theme::ERROR).add_modifier(Modifier::UNDERLINED)
+                };
+                Span::styled(raw, style)
+            }
+        })
+        .collect();
+    Line::from(spans)
+}
+
 fn render_tabs(frame: &mut Frame, app: &App, area: Rect) {
     let tabs = ["Params", "Headers", "Body", "Auth"];
     let selected = match app.active_tab {
@@ -223,15 +352,17 @@ fn render_tabs(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_tab_content(frame: &mut Frame, app: &App, area: Rect) {
+    // While the environment overlay is open, its variables own the key-value editor
+    let is_editing = app.edit_focus == EditFocus::KeyValue && !app.show_environments;
     match app.active_tab {
-        RequestTab::Params => render_kv_list(frame, app, area, &app.params, &app.params_editor),
-        RequestTab::Headers => render_kv_list(frame, app, area, &app.headers, &app.headers_editor),
+        RequestTab::Params => render_kv_list(frame, area, &app.params, &app.params_editor, is_editing),
+        RequestTab::Headers => render_kv_list(frame, area, &app.headers, &app.headers_editor, is_editing),
         RequestTab::Auth => render_auth_editor(frame, app, area),
         RequestTab::Body => render_body_editor(frame, app, area),
     }
 }
 
-fn render_kv_list(frame: &mut Frame, app: &App, area: Rect, items: &[KeyValue], editor: &KvEditor) {
+fn render_kv_list(frame: &mut Frame, area: Rect, items: &[KeyValue], editor: &KvEditor, is_editing: bool) {
     if items.is_empty() {
         let hint = Paragraph::new(Span::styled(
             "Press 'a' to add",
@@ -242,8 +373,6 @@ fn render_kv_list(frame: &mut Frame, app: &App, area: Rect, items: &[KeyValue],
         return;
     }
 
-    let is_editing = app.edit_focus == EditFocus::KeyValue;
-
     for (i, item) in items.iter().enumerate() {
         if i >= area.height as usize {
             break;
@@ -330,6 +459,115 @@ fn render_kv_row_editing(frame: &mut Frame, editor: &KvEditor, item: &KeyValue,
 }
 
 fn render_body_editor(frame: &mut Frame, app: &App, area: Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .split(area);
+
+    let mut type_line = vec![
+        Span::styled("< ", Style::default().fg(theme::TEXT_DIM)),
+        Span::styled(
+            app.body_type.label(),
+            Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" >", Style::default().fg(theme::TEXT_DIM)),
+        Span::styled("  (Tab to cycle)", Style::default().fg(theme::TEXT_DIM)),
+    ];
+    if app.body_type == BodyType::Multipart {
+        type_line.push(Span::styled("  @path uploads a file", Style::default().fg(theme::TEXT_DIM)));
+    }
+    if app.body_type == BodyType::GraphQl {
+        let schema = if app.has_schema() { "  schema loaded" } else { "  Ctrl+G fetches the schema" };
+        type_line.push(Span::styled(schema, Style::default().fg(theme::TEXT_DIM)));
+    }
+    if app.method == HttpMethod::Ws {
+        type_line.push(Span::styled("  Ctrl+S sends it as a message once connected", Style::default().fg(theme::TEXT_DIM)));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(type_line)).style(Style::default().bg(theme::BG)),
+        layout[0],
+    );
+
+    if app.body_type.is_form() {
+        let is_editing = app.edit_focus == EditFocus::KeyValue && !app.show_environments;
+        render_kv_list(frame, layout[1], &app.form, &app.form_editor, is_editing);
+    } else if app.body_type == BodyType::File {
+        render_body_file(frame, app, layout[1]);
+    } else if app.body_type == BodyType::GraphQl {
+        render_graphql_body(frame, app, layout[1]);
+    } else {
+        render_raw_body(frame, app, layout[1]);
+    }
+}
+
+fn render_body_file(frame: &mut Frame, app: &App, area: Rect) {
+    if app.body_file.is_empty() {
+        let hint = Paragraph::new(Span::styled(
+            "Press 'e' to choose a file",
+            Style::default().fg(theme::TEXT_DIM),
+        ))
+        .centered();
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let path = files::expand_home(&app.body_file);
+    let status = match app.body_file_size {
+        Some(size) => Span::styled(
+            format!("{}  {}", format_size(size), files::mime_type(&path)),
+            Style::default().fg(theme::TEXT_DIM),
+        ),
+        None => Span::styled("File not found", Style::default().fg(theme::ERROR)),
+    };
+    let lines = vec![
+        Line::from(Span::styled(app.body_file.as_str(), Style::default().fg(theme::TEXT))),
+        Line::from(status),
+    ];
+    frame.render_widget(Paragraph::new(lines).style(Style::default().bg(theme::BG)), area);
+}
+
+/// The query above its variables, each shown highlighted until edited.
+fn render_graphql_body(frame: &mut Frame, app: &App, area: Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(60),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(app.json_error.is_some() as u16),
+        ])
+        .split(area);
+
+    let is_editing = app.edit_focus == EditFocus::Body;
+    let editing_query = is_editing && !app.editing_variables;
+    let editing_variables = is_editing && app.editing_variables;
+
+    if editing_query {
+        frame.render_widget(&app.body_editor, layout[0]);
+    } else if app.body().is_empty() {
+        let hint = Paragraph::new(Span::styled("Press 'e' to edit the query", Style::default().fg(theme::TEXT_DIM)));
+        frame.render_widget(hint, layout[0]);
+    } else {
+        frame.render_widget(Paragraph::new(Text::from(app.body_preview.clone())), layout[0]);
+    }
+
+    let title_style = if editing_variables { theme::ACCENT } else { theme::TEXT_DIM };
+    frame.render_widget(
+        Paragraph::new(Span::styled("Variables (Shift+Tab switches)", Style::default().fg(title_style))),
+        layout[1],
+    );
+    if editing_variables {
+        frame.render_widget(&app.variables_editor, layout[2]);
+    } else {
+        frame.render_widget(Paragraph::new(Text::from(app.variables_preview.clone())), layout[2]);
+    }
+
+    if let Some(err) = &app.json_error {
+        frame.render_widget(Paragraph::new(Span::styled(err.as_str(), Style::default().fg(theme::ERROR))), layout[3]);
+    }
+}
+
+fn render_raw_body(frame: &mut Frame, app: &App, area: Rect) {
     let is_editing = app.edit_focus == EditFocus::Body;
     let body_text = app.body();
 
@@ -363,8 +601,7 @@ fn render_body_content(frame: &mut Frame, app: &App, area: Rect, is_editing: boo
     if is_editing {
         frame.render_widget(&app.body_editor, area);
     } else {
-        let content = format_json_if_valid(body_text);
-        let paragraph = Paragraph::new(content)
+        let paragraph = Paragraph::new(Text::from(app.body_preview.clone()))
             .style(Style::default().fg(theme::TEXT).bg(theme::BG));
         frame.render_widget(paragraph, area);
     }
@@ -372,11 +609,16 @@ fn render_body_content(frame: &mut Frame, app: &App, area: Rect, is_editing: boo
 
 fn render_auth_editor(frame: &mut Frame, app: &App, area: Rect) {
     let is_editing = app.edit_focus == EditFocus::Auth;
+    let options = auth_option_lines(app);
 
-    // Layout: type selector row + fields
+    // Layout: type selector row + grant, token or key location + fields
     let layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(if options.is_empty() { 0 } else { options.len() as u16 + 1 }),
+            Constraint::Min(0),
+        ])
         .split(area);
 
     // Auth type selector with navigation hint
@@ -394,46 +636,66 @@ fn render_auth_editor(frame: &mut Frame, app: &App, area: Rect) {
         layout[0],
     );
 
-    // Render fields based on auth type
+    frame.render_widget(
+        Paragraph::new(options).style(Style::default().bg(theme::BG)),
+        layout[1],
+    );
+
+    let fields = app.auth.fields();
+    if fields.is_empty() {
+        let hint = Paragraph::new(Span::styled(
+            "No authentication configured",
+            Style::default().fg(theme::TEXT_DIM),
+        ))
+        .centered();
+        frame.render_widget(hint, layout[2]);
+    } else {
+        render_auth_fields(frame, app, layout[2], is_editing, &fields);
+    }
+}
+
+/// An OAuth2 grant and whether a token is cached and for how long, or where
+/// an API key is sent.
+fn auth_option_lines(app: &App) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(theme::TEXT_DIM);
+    let option = |label: &'static str, value: &'static str| {
+        Line::from(vec![
+            Span::styled(label, dim),
+            Span::styled(value, Style::default().fg(theme::ACCENT)),
+            Span::styled("  (g to cycle)", dim),
+        ])
+    };
+
     match &app.auth {
-        AuthType::None => {
-            let hint = Paragraph::new(Span::styled(
-                "No authentication configured",
-                Style::default().fg(theme::TEXT_DIM),
-            ))
-            .centered();
-            frame.render_widget(hint, layout[1]);
-        }
-        AuthType::Basic { username, password } => {
-            render_auth_fields(
-                frame,
-                app,
-                layout[1],
-                is_editing,
-                &[("Username", username), ("Password", password)],
-                true, // mask second field
-            );
-        }
-        AuthType::Bearer { token } => {
-            render_auth_fields(
-                frame,
-                app,
-                layout[1],
-                is_editing,
-                &[("Token", token)],
-                true, // mask field
-            );
-        }
-        AuthType::ApiKey { key, value } => {
-            render_auth_fields(
-                frame,
-                app,
-                layout[1],
-                is_editing,
-                &[("Header Name", key), ("Header Value", value)],
-                false, // don't mask
-            );
+        AuthType::OAuth2(config) => {
+            let (status, color) = match app.oauth_token() {
+                None => ("none yet, fetched on send".to_string(), theme::TEXT_DIM),
+                Some(token) => match token.expires_in() {
+                    None => ("valid, no expiry given".to_string(), theme::STATUS_SUCCESS),
+                    Some(_) if !token.is_fresh() => ("expired, renewed on send".to_string(), theme::STATUS_CLIENT_ERROR),
+                    Some(left) => (format!("valid, expires in {}", format_remaining(left)), theme::STATUS_SUCCESS),
+                },
+            };
+            let mut token_line = vec![Span::styled("Token: ", dim), Span::styled(status, Style::default().fg(color))];
+            if app.oauth_token().is_some() {
+                token_line.push(Span::styled("  (x to forget)", dim));
+            }
+            vec![option("Grant: ", config.grant.label()), Line::from(token_line)]
         }
+        AuthType::ApiKey { location, .. } => vec![option("Send as: ", location.label())],
+        _ => vec![],
+    }
+}
+
+/// `45s`, `12m 5s`, `3h 20m`
+fn format_remaining(left: Duration) -> String {
+    let secs = left.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
     }
 }
 
@@ -443,7 +705,6 @@ fn render_auth_fields(
     area: Rect,
     is_editing: bool,
     fields: &[(&str, &str)],
-    mask_sensitive: bool,
 ) {
     let constraints: Vec<Constraint> = fields
         .iter()
@@ -456,15 +717,10 @@ fn render_auth_fields(
         .constraints(constraints)
         .split(area);
 
-    let label_width = 14u16;
+    let label_width = fields.iter().map(|(label, _)| label.len() as u16 + 2).max().unwrap_or(0).max(14);
 
     for (i, (label, value)) in fields.iter().enumerate() {
-        let is_first_field = i == 0;
-        let is_selected = match app.auth_editor.field {
-            AuthField::First => is_first_field,
-            AuthField::Second => !is_first_field,
-        };
-        let is_active = is_editing && is_selected;
+        let is_active = is_editing && app.auth_editor.field == i;
 
         let bg = if is_active { theme::BG_HIGHLIGHT } else { theme::BG };
 
@@ -481,20 +737,12 @@ fn render_auth_fields(
         );
 
         // Value: show TextArea when actively editing, otherwise show text
-        if is_active {
-            let input = if is_first_field {
-                &app.auth_editor.first_input
-            } else {
-                &app.auth_editor.second_input
-            };
+        if let Some(input) = app.auth_editor.inputs.get(i).filter(|_| is_active) {
             frame.render_widget(input, chunks[1]);
         } else {
-            // Mask sensitive fields: second field (password) or single field (bearer token)
-            let is_single_field = fields.len() == 1;
-            let should_mask = mask_sensitive && (is_single_field || !is_first_field) && !value.is_empty();
             let display = if value.is_empty() {
                 "(empty)".to_string()
-            } else if should_mask {
+            } else if AuthType::is_secret(label) {
                 "•".repeat(value.len().min(20))
             } else {
                 (*value).to_string()
@@ -508,25 +756,80 @@ fn render_auth_fields(
     }
 }
 
-fn render_response(frame: &mut Frame, app: &App, area: Rect) {
+fn render_response(frame: &mut Frame, app: &mut App, area: Rect) {
     let focused = app.focused_panel == Panel::Response;
     let border = if focused { theme::BORDER_FOCUSED } else { theme::BORDER };
 
-    let right_title: Line = match &app.request_state {
+    let right_title: Line = match app.request_state() {
         RequestState::Idle => Line::from(""),
         RequestState::Loading => Line::from(Span::styled(" ● Loading ", Style::default().fg(theme::STATUS_LOADING))),
         RequestState::Success(resp) => {
             let status_col = status_color(resp.status);
-            Line::from(vec![
+            let mut spans = vec![
                 Span::styled(format!(" {} {} ", resp.status, resp.status_text), Style::default().fg(theme::BG).bg(status_col).add_modifier(Modifier::BOLD)),
-                Span::styled(format!("  {}  {} ", resp.elapsed_display(), resp.size_display()), Style::default().fg(theme::TEXT_DIM)),
-            ])
+            ];
+            // A WebSocket's handshake answers 101
+            let (live, noun) = if resp.status == 101 { ("Connected", "messages") } else { ("Live", "events") };
+            match &resp.events {
+                Some(log) if app.is_streaming() => spans.push(Span::styled(
+                    format!("  ● {} · {} {} ", live, log.events.len(), noun),
+                    Style::default().fg(theme::STATUS_LOADING),
+                )),
+                Some(log) => spans.push(Span::styled(
+                    format!("  {} {}  {}  {} ", log.events.len(), noun, resp.elapsed_display(), resp.size_display()),
+                    Style::default().fg(theme::TEXT_DIM),
+                )),
+                None => spans.push(Span::styled(format!("  {}  {} ", resp.elapsed_display(), resp.size_display()), Style::default().fg(theme::TEXT_DIM))),
+            }
+            Line::from(spans)
         },
         RequestState::Error(_) => Line::from(Span::styled(" ✕ Error ", Style::default().fg(theme::BG).bg(theme::STATUS_SERVER_ERROR).add_modifier(Modifier::BOLD))),
+        RequestState::Cancelled => Line::from(Span::styled(" ⊘ Cancelled ", Style::default().fg(theme::TEXT_DIM))),
     };
 
+    let mut title = vec![Span::raw(" Response ")];
+    if let Some(tree) = &app.json_tree
+        && app.tree_view_active()
+    {
+        title.push(Span::styled(format!("{} ", tree.cursor_path()), Style::default().fg(theme::ACCENT)));
+    }
+    if let RequestState::Success(resp) = app.request_state()
+        && app.hex_view_active()
+        && app.response_tab == ResponseTab::Body
+    {
+        let kind = match binary::detect_image(resp.bytes()) {
+            Some(image) => format!("hex · {} ", image.describe()),
+            None => "hex ".to_string(),
+        };
+        title.push(Span::styled(kind, Style::default().fg(theme::TEXT_DIM)));
+    }
+    if let RequestState::Success(resp) = app.request_state()
+        && app.response_tab == ResponseTab::Body
+    {
+        let held = resp.bytes().len();
+        let shown = if app.hex_view_active() { held.min(binary::HEX_MAX_BYTES) } else { held };
+        if resp.spill.is_some() || shown < held {
+            let shown = format!("showing first {} ", format_size(shown as u64));
+            title.push(Span::styled(shown, Style::default().fg(theme::STATUS_CLIENT_ERROR)));
+        }
+    }
+    if let Some(search) = &app.search
+        && app.response_tab == ResponseTab::Body
+    {
+        let (text, color) = match search.position() {
+            Some(pos) => (format!("[{}/{}] ", pos, search.matches().len()), theme::ACCENT),
+            None => ("[no matches] ".to_string(), theme::ERROR),
+        };
+        title.push(Span::styled(text, Style::default().fg(color)));
+    }
+    if let Some(err) = &app.filter_error {
+        title.push(Span::styled(format!("jq: {} ", err), Style::default().fg(theme::ERROR)));
+    } else if !app.response_filter.is_empty() {
+        title.push(Span::styled(format!("jq: {} ", app.response_filter), Style::default().fg(theme::TEXT_DIM)));
+    }
+
     let block = Block::default()
-        .title(" Response ")
+        .title(Line::from(title))
         .title(right_title.alignment(Alignment::Right))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(border))
@@ -535,7 +838,16 @@ fn render_response(frame: &mut Frame, app: &App, area: Rect) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    match &app.request_state {
+    // Taken out for the duration so its list state can be updated while
+    // the response itself is borrowed from `app`
+    let show_tree = app.tree_view_active();
+    let mut tree = app.json_tree.take();
+    if app.is_streaming() && app.response_tab == ResponseTab::Body {
+        // Less the tab bar above the body
+        app.follow_events(inner.height.saturating_sub(2) as usize);
+    }
+
+    match app.request_state() {
         RequestState::Idle => {
             let text = Paragraph::new(Text::from(vec![
                 Line::from(""),
@@ -547,23 +859,60 @@ fn render_response(frame: &mut Frame, app: &App, area: Rect) {
             frame.render_widget(text, inner);
         }
         RequestState::Loading => {
+            let status = match app.download_progress() {
+                Some((received, total, elapsed)) if received > 0 => {
+                    let rate = received as f64 / elapsed.as_secs_f64().max(0.001);
+                    let amount = match total {
+                        Some(total) if total > 0 => format!(
+                            "{} of {} ({}%)",
+                            format_size(received),
+                            format_size(total),
+                            received.saturating_mul(100) / total
+                        ),
+                        _ => format_size(received),
+                    };
+                    format!("Receiving {} · {}/s", amount, format_size(rate as u64))
+                }
+                _ => app.loading_status().unwrap_or("Sending request...").to_string(),
+            };
             let text = Paragraph::new(Text::from(vec![
                 Line::from(""),
-                Line::from(Span::styled("Sending request...", Style::default().fg(theme::STATUS_LOADING).add_modifier(Modifier::BOLD))),
+                Line::from(Span::styled(status, Style::default().fg(theme::STATUS_LOADING).add_modifier(Modifier::BOLD))),
+                Line::from(""),
+                Line::from(Span::styled("Press Ctrl+X to cancel", Style::default().fg(theme::TEXT_DIM))),
             ]))
+            .wrap(Wrap { trim: false })
             .centered();
             frame.render_widget(text, inner);
         }
         RequestState::Success(resp) => {
-            let formatted = resp.formatted_body();
-            let lines: Vec<Line> = formatted
-                .lines()
-                .skip(app.response_scroll)
-                .take(inner.height as usize)
-                .map(|l| Line::from(Span::styled(l, Style::default().fg(theme::TEXT))))
-                .collect();
-
-            frame.render_widget(Paragraph::new(Text::from(lines)).style(Style::default().bg(theme::BG)), inner);
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(2), Constraint::Min(0)])
+                .split(inner);
+
+            render_response_tabs(frame, app, resp, layout[0]);
+            match app.response_tab {
+                ResponseTab::Body if show_tree => {
+                    if let Some(tree) = tree.as_mut() {
+                        render_json_tree(frame, tree, layout[1]);
+                    }
+                }
+                ResponseTab::Body => render_response_body(frame, app, layout[1]),
+                ResponseTab::Headers => render_response_headers(frame, app, layout[1]),
+                ResponseTab::Cookies => render_response_cookies(frame, app, resp, layout[1]),
+                ResponseTab::Timing => render_response_timing(frame, resp, layout[1]),
+            }
+        }
+        RequestState::Cancelled => {
+            let text = Paragraph::new(Text::from(vec![
+                Line::from(""),
+                Line::from(Span::styled("Request cancelled", Style::default().fg(theme::TEXT_DIM).add_modifier(Modifier::ITALIC))),
+                Line::from(""),
+                Line::from(Span::styled("Press Ctrl+S to send again", Style::default().fg(theme::TEXT_DIM))),
+            ]))
+            .centered();
+            frame.render_widget(text, inner);
         }
         RequestState::Error(err) => {
             let text = Paragraph::new(Text::from(vec![
@@ -576,6 +925,201 @@ fn render_response(frame: &mut Frame, app: &App, area: Rect) {
             frame.render_widget(text, inner);
         }
     }
+
+    app.json_tree = tree;
+}
+
+fn render_response_tabs(frame: &mut Frame, app: &App, resp: &Response, area: Rect) {
+    let titles = vec![
+        Line::from("Body"),
+        Line::from(format!("Headers ({})", resp.headers.len())),
+        Line::from(format!("Cookies ({})", resp.cookies().len())),
+        Line::from("Timing"),
+    ];
+    let selected = match app.response_tab {
+        ResponseTab::Body => 0,
+        ResponseTab::Headers => 1,
+        ResponseTab::Cookies => 2,
+        ResponseTab::Timing => 3,
+    };
+
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().fg(theme::TEXT_DIM))
+        .highlight_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .divider("│");
+
+    frame.render_widget(tabs, area);
+}
+
+fn render_response_body(frame: &mut Frame, app: &App, area: Rect) {
+    let matches = app.search.as_ref().map(|s| s.matches()).unwrap_or_default();
+    let current = app.search.as_ref().and_then(|s| s.current());
+
+    let lines: Vec<Line> = app
+        .response_lines
+        .iter()
+        .enumerate()
+        .skip(app.response_scroll)
+        .take(area.height as usize)
+        .map(|(i, line)| {
+            let first = matches.partition_point(|m| m.line < i);
+            let ranges: Vec<_> = matches[first..]
+                .iter()
+                .take_while(|m| m.line == i)
+                .map(|m| {
+                    let bg = if Some(*m) == current { theme::SEARCH_CURRENT } else { theme::SEARCH_MATCH };
+                    (m.start, m.end, Style::default().bg(bg))
+                })
+                .collect();
+            if ranges.is_empty() { line.clone() } else { highlight::mark_ranges(line, &ranges) }
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(Text::from(lines)).style(Style::default().bg(theme::BG)), area);
+}
+
+fn render_json_tree(frame: &mut Frame, tree: &mut JsonTree, area: Rect) {
+    let punct = |text: String| Span::styled(text, Style::default().fg(theme::SYNTAX_PUNCT));
+
+    let items: Vec<ListItem> = tree
+        .rows()
+        .iter()
+        .map(|row| {
+            let mut spans = vec![Span::raw("  ".repeat(row.depth))];
+            if let Some(key) = &row.key {
+                spans.push(Span::styled(
+                    serde_json::to_string(key).unwrap_or_default(),
+                    Style::default().fg(theme::SYNTAX_KEY),
+                ));
+                spans.push(punct(": ".to_string()));
+            }
+            match &row.kind {
+                RowKind::Open { bracket, len, collapsed: true } => {
+                    spans.push(Span::styled(
+                        RowKind::summary(*bracket, *len),
+                        Style::default().fg(theme::TEXT_DIM).add_modifier(Modifier::ITALIC),
+                    ));
+                }
+                RowKind::Open { bracket, .. } => spans.push(punct(bracket.to_string())),
+                RowKind::Close(bracket) => spans.push(punct(bracket.to_string())),
+                RowKind::Scalar(text) => {
+                    let color = if text.starts_with('"') {
+                        theme::SYNTAX_STRING
+                    } else {
+                        highlight::scalar_color(text)
+                    };
+                    spans.push(Span::styled(text.clone(), Style::default().fg(color)));
+                }
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(Style::default().bg(theme::BG).fg(theme::TEXT))
+        .highlight_style(Style::default().bg(theme::BG_HIGHLIGHT));
+
+    frame.render_stateful_widget(list, area, &mut tree.state);
+}
+
+fn render_response_headers(frame: &mut Frame, app: &App, area: Rect) {
+    let headers = app.visible_headers();
+
+    let (list_area, filter_area) = if app.header_filter.is_empty() {
+        (area, None)
+    } else {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+        (layout[0], Some(layout[1]))
+    };
+
+    let lines: Vec<Line> = headers
+        .iter()
+        .skip(app.response_scroll)
+        .take(list_area.height as usize)
+        .map(|(name, value)| {
+            Line::from(vec![
+                Span::styled(name.as_str(), Style::default().fg(theme::ACCENT)),
+                Span::styled(": ", Style::default().fg(theme::TEXT_DIM)),
+                Span::styled(value.as_str(), Style::default().fg(theme::TEXT)),
+            ])
+        })
+        .collect();
+
+    if lines.is_empty() {
+        let hint = Paragraph::new(Span::styled("No matching headers", Style::default().fg(theme::TEXT_DIM)))
+            .centered();
+        frame.render_widget(hint, list_area);
+    } else {
+        frame.render_widget(Paragraph::new(Text::from(lines)).style(Style::default().bg(theme::BG)), list_area);
+    }
+
+    if let Some(filter_area) = filter_area {
+        let filter = Line::from(vec![
+            Span::styled("/", Style::default().fg(theme::ACCENT)),
+            Span::styled(app.header_filter.as_str(), Style::default().fg(theme::TEXT)),
+            Span::styled(format!("  ({} matches)", headers.len()), Style::default().fg(theme::TEXT_DIM)),
+        ]);
+        frame.render_widget(Paragraph::new(filter), filter_area);
+    }
+}
+
+fn render_response_cookies(frame: &mut Frame, app: &App, resp: &Response, area: Rect) {
+    let cookies = resp.cookies();
+    if cookies.is_empty() {
+        let hint = Paragraph::new(Span::styled("No cookies set", Style::default().fg(theme::TEXT_DIM)))
+            .centered();
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let lines: Vec<Line> = cookies
+        .iter()
+        .flat_map(|cookie| {
+            [
+                Line::from(vec![
+                    Span::styled(cookie.name.clone(), Style::default().fg(theme::ACCENT)),
+                    Span::styled(" = ", Style::default().fg(theme::TEXT_DIM)),
+                    Span::styled(cookie.value.clone(), Style::default().fg(theme::TEXT)),
+                ]),
+                Line::from(Span::styled(
+                    format!("  {}", cookie.attributes().join("; ")),
+                    Style::default().fg(theme::TEXT_DIM),
+                )),
+            ]
+        })
+        .skip(app.response_scroll)
+        .collect();
+
+    frame.render_widget(Paragraph::new(Text::from(lines)).style(Style::default().bg(theme::BG)), area);
+}
+
+fn render_response_timing(frame: &mut Frame, resp: &Response, area: Rect) {
+    let download = resp.elapsed.saturating_sub(resp.time_to_headers);
+    let rows = [
+        ("Waiting (TTFB)", format_duration(resp.time_to_headers)),
+        ("Download", format_duration(download)),
+        ("Total", format_duration(resp.elapsed)),
+        ("", String::new()),
+        ("Size", resp.size_display()),
+        ("Protocol", resp.version.clone()),
+        ("Remote", resp.remote_addr.clone().unwrap_or_else(|| "-".to_string())),
+    ];
+
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:16}", label), Style::default().fg(theme::TEXT_DIM)),
+                Span::styled(value, Style::default().fg(theme::TEXT)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(Text::from(lines)).style(Style::default().bg(theme::BG)), area);
 }
 
 fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
@@ -589,14 +1133,28 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         EditFocus::KeyValue => Span::styled(" INSERT ", Style::default().fg(theme::BG).bg(theme::METHOD_POST)),
         EditFocus::Body => Span::styled(" INSERT ", Style::default().fg(theme::BG).bg(theme::METHOD_PUT)),
         EditFocus::Auth => Span::styled(" INSERT ", Style::default().fg(theme::BG).bg(theme::METHOD_DELETE)),
+        EditFocus::Prompt => Span::styled(" INSERT ", Style::default().fg(theme::BG).bg(theme::ACCENT)),
     };
 
-    let hints: Vec<Span> = if app.edit_focus == EditFocus::Body {
+    let hints: Vec<Span> = if app.edit_focus == EditFocus::Body && app.body_type == BodyType::GraphQl {
+        vec![
+            Span::styled("esc", key), Span::styled(":done ", desc),
+            Span::styled("tab", key), Span::styled(":complete ", desc),
+            Span::styled("S-tab", key), Span::styled(":query/vars ", desc),
+            Span::styled("C-G", key), Span::styled(":schema ", desc),
+            Span::styled("C-S", key), Span::styled(":send", desc),
+        ]
+    } else if app.edit_focus == EditFocus::Body {
         vec![
             Span::styled("esc", key), Span::styled(":done ", desc),
             Span::styled("C-F", key), Span::styled(":fmt ", desc),
             Span::styled("C-S", key), Span::styled(":send", desc),
         ]
+    } else if app.edit_focus == EditFocus::Prompt {
+        vec![
+            Span::styled("enter", key), Span::styled(":ok ", desc),
+            Span::styled("esc", key), Span::styled(":cancel", desc),
+        ]
     } else if app.is_editing() {
         vec![
             Span::styled("Esc", key), Span::styled(":done ", desc),
@@ -608,30 +1166,45 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
                 Span::styled("j/k", key), Span::styled(":nav ", desc),
                 Span::styled("enter", key), Span::styled(":select ", desc),
                 Span::styled("n", key), Span::styled(":new ", desc),
+                Span::styled("f", key), Span::styled(":folder ", desc),
+                Span::styled("m", key), Span::styled(":move ", desc),
                 Span::styled("d", key), Span::styled(":del", desc),
             ],
             Panel::RequestEditor => vec![
                 Span::styled("i", key), Span::styled(":url ", desc),
                 Span::styled("1-4", key), Span::styled(":tab ", desc),
                 Span::styled("a", key), Span::styled(":add ", desc),
+                Span::styled("c", key), Span::styled(":code ", desc),
                 Span::styled("C-S", key), Span::styled(":send", desc),
             ],
             Panel::Response => vec![
                 Span::styled("j/k", key), Span::styled(":scroll ", desc),
-                Span::styled("g/G", key), Span::styled(":jump", desc),
+                Span::styled("g/G", key), Span::styled(":jump ", desc),
+                Span::styled("1-4", key), Span::styled(":tab", desc),
             ],
         }
     };
 
-    let right = vec![
+    let mut right = vec![];
+    if let Some(name) = app.active_environment_name() {
+        right.push(Span::styled(format!("{} ", name), Style::default().fg(theme::METHOD_HEAD)));
+        right.push(Span::styled("│ ", dim));
+    }
+    right.extend([
         Span::styled("?", key), Span::styled(":help ", desc),
         Span::styled("q", key), Span::styled(":quit ", desc),
         Span::styled("│ ", dim),
         Span::styled("courier", Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD)),
-    ];
+    ]);
 
     let mut left: Vec<Span> = vec![mode, Span::styled(" ", desc)];
-    left.extend(hints);
+    match &app.message {
+        Some(message) => {
+            let color = if message.is_error { theme::ERROR } else { theme::METHOD_GET };
+            left.push(Span::styled(message.text.as_str(), Style::default().fg(color)));
+        }
+        None => left.extend(hints),
+    }
 
     let left_len: usize = left.iter().map(|s| s.width()).sum();
     let right_len: usize = right.iter().map(|s| s.width()).sum();
@@ -644,35 +1217,68 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Paragraph::new(Line::from(all)).style(Style::default().bg(theme::BG)), area);
 }
 
-fn render_help_overlay(frame: &mut Frame, _app: &App, area: Rect) {
-    const HELP_LINES: &[(&str, &str)] = &[
-        ("", "Navigation"),
-        ("Tab/h/l", "Switch panels"),
-        ("j/k", "Navigate/scroll"),
-        ("1-4", "Switch tabs"),
-        ("", ""),
-        ("", "Requests"),
-        ("Ctrl+S", "Send request"),
-        ("i", "Edit URL"),
-        ("a", "Add param/header"),
-        ("e", "Edit body"),
-        ("Enter", "Edit selected"),
-        ("n", "New request"),
-        ("d", "Delete"),
-        ("", ""),
-        ("", "Authentication"),
-        ("Tab", "Cycle auth type"),
-        ("Enter", "Edit auth fields"),
-        ("", ""),
-        ("", "Body Editing"),
-        ("Ctrl+F", "Format JSON"),
-        ("Esc", "Stop editing"),
-        ("", ""),
-        ("", "General"),
-        ("?", "Toggle help"),
-        ("q", "Quit"),
-    ];
-
+pub const HELP_LINES: &[(&str, &str)] = &[
+    ("", "Navigation"),
+    ("Tab/h/l", "Switch panels"),
+    ("j/k", "Navigate/scroll"),
+    ("1-4", "Switch tabs"),
+    ("", ""),
+    ("", "Requests"),
+    ("Ctrl+S", "Send request"),
+    ("Ctrl+X", "Cancel request / stop stream / close socket"),
+    ("Ctrl+G", "Fetch GraphQL schema"),
+    ("i", "Edit URL"),
+    ("a", "Add param/header"),
+    ("e", "Edit body"),
+    ("Enter", "Edit selected"),
+    ("c", "Export as code (y to copy)"),
+    ("n", "New request"),
+    ("d", "Delete"),
+    ("", ""),
+    ("", "Sidebar"),
+    ("Enter/Space", "Open/close folder"),
+    ("f", "New folder"),
+    ("r", "Rename"),
+    ("m", "Mark request to move"),
+    ("p", "Move marked here"),
+    ("I", "Import curl command"),
+    ("", ""),
+    ("", "Response"),
+    ("1-4", "Body/Headers/Cookies/Timing"),
+    ("/", "Filter headers"),
+    ("f", "jq filter on body"),
+    ("/ or ?", "Search body (regex)"),
+    ("n/N", "Next/previous match"),
+    ("t", "Toggle JSON tree view"),
+    ("x", "Toggle hex view"),
+    ("w", "Save body to file"),
+    ("p", "Ping WebSocket"),
+    ("Enter/Space", "Fold/unfold (tree)"),
+    ("zo/zc/za", "Open/close/toggle fold"),
+    ("zR/zM", "Open/close all folds"),
+    ("", ""),
+    ("", "Body & Authentication"),
+    ("Tab", "Cycle body/auth type"),
+    ("Enter", "Edit auth fields"),
+    ("g / x", "Grant or key location / forget token"),
+    ("", ""),
+    ("", "Environments"),
+    ("E", "Manage environments"),
+    ("Ctrl+E", "Cycle active environment"),
+    ("s", "Activate (in manager)"),
+    ("S", "Client settings"),
+    ("C", "Manage cookies"),
+    ("", ""),
+    ("", "Body Editing"),
+    ("Ctrl+F", "Format JSON"),
+    ("Esc", "Stop editing"),
+    ("", ""),
+    ("", "General"),
+    ("?", "Toggle help"),
+    ("q", "Quit"),
+];
+
+fn render_help_overlay(frame: &mut Frame, app: &App, area: Rect) {
     let (w, h) = (50, 28);
     let help_area = Rect {
         x: area.width.saturating_sub(w) / 2,
@@ -685,6 +1291,7 @@ fn render_help_overlay(frame: &mut Frame, _app: &App, area: Rect) {
 
     let items: Vec<ListItem> = HELP_LINES
         .iter()
+        .skip(app.help_scroll)
         .map(|&(key, desc)| {
             if key.is_empty() && desc.is_empty() {
                 ListItem::new(Line::from(""))
@@ -723,6 +1330,320 @@ fn render_help_overlay(frame: &mut Frame, _app: &App, area: Rect) {
     frame.render_widget(list, help_area);
 }
 
+fn render_environments_overlay(frame: &mut Frame, app: &App, area: Rect) {
+    let (w, h) = (70, 20);
+    let env_area = Rect {
+        x: area.width.saturating_sub(w) / 2,
+        y: area.height.saturating_sub(h) / 2,
+        width: w.min(area.width),
+        height: h.min(area.height),
+    };
+
+    frame.render_widget(Clear, env_area);
+
+    let position = if app.environments.is_empty() {
+        String::new()
+    } else {
+        format!(" {}/{} ", app.environment_cursor + 1, app.environments.len())
+    };
+
+    let block = Block::default()
+        .title(" Environments ")
+        .title(Line::from(position).alignment(Alignment::Right))
+        .title_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+        .style(Style::default().bg(theme::BG));
+
+    let inner = block.inner(env_area);
+    frame.render_widget(block, env_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let key = Style::default().fg(theme::TEXT);
+    let desc = Style::default().fg(theme::TEXT_DIM);
+    let hints = Line::from(vec![
+        Span::styled("n", key), Span::styled(":new ", desc),
+        Span::styled("r", key), Span::styled(":rename ", desc),
+        Span::styled("D", key), Span::styled(":delete ", desc),
+        Span::styled("s", key), Span::styled(":activate ", desc),
+        Span::styled("tab", key), Span::styled(":next ", desc),
+        Span::styled("a", key), Span::styled(":add var", desc),
+    ]);
+    frame.render_widget(Paragraph::new(hints), layout[2]);
+
+    let Some(env) = app.environments.get(app.environment_cursor) else {
+        let hint = Paragraph::new(Span::styled(
+            "No environments. Press 'n' to create one",
+            Style::default().fg(theme::TEXT_DIM),
+        ))
+        .centered();
+        frame.render_widget(hint, layout[1]);
+        return;
+    };
+
+    let mut header = vec![Span::styled(
+        env.name.as_str(),
+        Style::default().fg(theme::TEXT).add_modifier(Modifier::BOLD),
+    )];
+    if app.active_environment == Some(app.environment_cursor) {
+        header.push(Span::styled("  ● active", Style::default().fg(theme::METHOD_GET)));
+    }
+    frame.render_widget(Paragraph::new(Line::from(header)), layout[0]);
+
+    let is_editing = app.edit_focus == EditFocus::KeyValue;
+    render_kv_list(frame, layout[1], &env.variables, &app.env_editor, is_editing);
+}
+
+fn render_settings_overlay(frame: &mut Frame, app: &App, area: Rect) {
+    let (w, h) = (70, 16);
+    let settings_area = Rect {
+        x: area.width.saturating_sub(w) / 2,
+        y: area.height.saturating_sub(h) / 2,
+        width: w.min(area.width),
+        height: h.min(area.height),
+    };
+
+    frame.render_widget(Clear, settings_area);
+
+    let block = Block::default()
+        .title(" Client Settings ")
+        .title_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+        .style(Style::default().bg(theme::BG));
+
+    let inner = block.inner(settings_area);
+    frame.render_widget(block, settings_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let scopes = [SettingsScope::Global, SettingsScope::Request];
+    let tabs = Tabs::new(scopes.iter().map(|s| Line::from(s.title())).collect::<Vec<_>>())
+        .select(scopes.iter().position(|&s| s == app.settings_scope).unwrap_or(0))
+        .style(Style::default().fg(theme::TEXT_DIM))
+        .highlight_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .divider("│");
+    frame.render_widget(tabs, layout[0]);
+
+    let effective = app.effective_settings();
+    let items: Vec<ListItem> = Setting::ALL
+        .iter()
+        .map(|&setting| {
+            let (value, style) = match app.settings_scope {
+                SettingsScope::Global => (app.settings.display(setting), Style::default().fg(theme::TEXT)),
+                SettingsScope::Request if app.request_settings.get(setting).is_some() => {
+                    (effective.display(setting), Style::default().fg(theme::ACCENT))
+                }
+                SettingsScope::Request => (
+                    format!("{} (global)", effective.display(setting)),
+                    Style::default().fg(theme::TEXT_DIM),
+                ),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:18}", setting.label()), Style::default().fg(theme::TEXT_DIM)),
+                Span::styled(value, style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(theme::BG_HIGHLIGHT).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ")
+        .highlight_spacing(HighlightSpacing::Always);
+    let mut state = ListState::default().with_selected(Some(app.settings_cursor));
+    frame.render_stateful_widget(list, layout[1], &mut state);
+
+    let key = Style::default().fg(theme::TEXT);
+    let desc = Style::default().fg(theme::TEXT_DIM);
+    let reset = if app.settings_scope == SettingsScope::Request { ":use global " } else { ":default " };
+    let hints = Line::from(vec![
+        Span::styled("tab", key), Span::styled(":scope ", desc),
+        Span::styled("enter", key), Span::styled(":edit ", desc),
+        Span::styled("space", key), Span::styled(":toggle ", desc),
+        Span::styled("d", key), Span::styled(reset, desc),
+    ]);
+    frame.render_widget(Paragraph::new(hints), layout[2]);
+}
+
+fn render_cookies_overlay(frame: &mut Frame, app: &App, area: Rect) {
+    let (w, h) = (80, 20);
+    let cookies_area = Rect {
+        x: area.width.saturating_sub(w) / 2,
+        y: area.height.saturating_sub(h) / 2,
+        width: w.min(area.width),
+        height: h.min(area.height),
+    };
+
+    frame.render_widget(Clear, cookies_area);
+
+    let title = match app.active_environment_name() {
+        Some(name) => format!(" Cookies ({}) ", name),
+        None => " Cookies ".to_string(),
+    };
+    let block = Block::default()
+        .title(title)
+        .title_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+        .style(Style::default().bg(theme::BG));
+
+    let inner = block.inner(cookies_area);
+    frame.render_widget(block, cookies_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let cookies = app.cookie_jar().cookies();
+    if cookies.is_empty() {
+        let empty = Paragraph::new("No cookies yet. Responses that set cookies fill this jar.")
+            .style(Style::default().fg(theme::TEXT_DIM));
+        frame.render_widget(empty, layout[0]);
+    } else {
+        let items: Vec<ListItem> = cookies
+            .iter()
+            .map(|cookie| {
+                let mut flags = vec![];
+                if cookie.secure {
+                    flags.push("Secure");
+                }
+                if cookie.http_only {
+                    flags.push("HttpOnly");
+                }
+                if cookie.expires_at.is_none() {
+                    flags.push("Session");
+                }
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:24}", cookie.domain), Style::default().fg(theme::TEXT_DIM)),
+                    Span::styled(format!("{:8}", cookie.path), Style::default().fg(theme::TEXT_DIM)),
+                    Span::styled(cookie.name.clone(), Style::default().fg(theme::ACCENT)),
+                    Span::styled("=", Style::default().fg(theme::TEXT_DIM)),
+                    Span::styled(cookie.value.clone(), Style::default().fg(theme::TEXT)),
+                    Span::styled(format!(" {}", flags.join(" ")), Style::default().fg(theme::TEXT_DIM)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .highlight_style(Style::default().bg(theme::BG_HIGHLIGHT).add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ")
+            .highlight_spacing(HighlightSpacing::Always);
+        let mut state = ListState::default().with_selected(Some(app.cookie_cursor));
+        frame.render_stateful_widget(list, layout[0], &mut state);
+    }
+
+    let key = Style::default().fg(theme::TEXT);
+    let desc = Style::default().fg(theme::TEXT_DIM);
+    let hints = Line::from(vec![
+        Span::styled("enter", key), Span::styled(":edit value ", desc),
+        Span::styled("d", key), Span::styled(":delete ", desc),
+        Span::styled("D", key), Span::styled(":clear domain ", desc),
+        Span::styled("x", key), Span::styled(":clear all ", desc),
+    ]);
+    frame.render_widget(Paragraph::new(hints), layout[1]);
+}
+
+fn render_export_overlay(frame: &mut Frame, export: &ExportView, area: Rect) {
+    let export_area = Rect {
+        x: area.width / 10,
+        y: area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+
+    frame.render_widget(Clear, export_area);
+
+    let block = Block::default()
+        .title(" Export ")
+        .title_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+        .style(Style::default().bg(theme::BG_HIGHLIGHT));
+
+    let inner = block.inner(export_area);
+    frame.render_widget(block, export_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let titles: Vec<Line> = Language::ALL.iter().map(|l| Line::from(l.name())).collect();
+    let selected = Language::ALL.iter().position(|&l| l == export.language).unwrap_or(0);
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().fg(theme::TEXT_DIM))
+        .highlight_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .divider("│");
+    frame.render_widget(tabs, layout[0]);
+
+    let lines: Vec<Line> = export
+        .code
+        .lines()
+        .skip(export.scroll)
+        .map(|l| Line::from(Span::styled(l, Style::default().fg(theme::TEXT))))
+        .collect();
+    frame.render_widget(Paragraph::new(Text::from(lines)), layout[1]);
+
+    let key = Style::default().fg(theme::TEXT);
+    let desc = Style::default().fg(theme::TEXT_DIM);
+    let hints = Line::from(vec![
+        Span::styled("tab", key), Span::styled(":language ", desc),
+        Span::styled("j/k", key), Span::styled(":scroll ", desc),
+        Span::styled("y", key), Span::styled(":copy ", desc),
+        Span::styled("esc", key), Span::styled(":close", desc),
+    ]);
+    frame.render_widget(Paragraph::new(hints), layout[2]);
+}
+
+fn render_prompt(frame: &mut Frame, prompt: &Prompt, area: Rect) {
+    let (w, h) = (50, 3);
+    let prompt_area = Rect {
+        x: area.width.saturating_sub(w) / 2,
+        y: area.height.saturating_sub(h) / 2,
+        width: w.min(area.width),
+        height: h.min(area.height),
+    };
+
+    frame.render_widget(Clear, prompt_area);
+
+    let block = Block::default()
+        .title(format!(" {} ", prompt.kind.title()))
+        .title_style(Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::BORDER_FOCUSED))
+        .style(Style::default().bg(theme::BG_HIGHLIGHT));
+
+    let inner = block.inner(prompt_area);
+    frame.render_widget(block, prompt_area);
+    frame.render_widget(&prompt.input, inner);
+}
+
+/// Vim-style `/pattern` line over the status bar, so hits stay visible while typing.
+fn render_search_prompt(frame: &mut Frame, prompt: &Prompt, area: Rect) {
+    let marker = if prompt.kind == PromptKind::SearchBackward { "?" } else { "/" };
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(Span::styled(marker, Style::default().fg(theme::ACCENT).add_modifier(Modifier::BOLD)))
+            .style(Style::default().bg(theme::BG)),
+        layout[0],
+    );
+    frame.render_widget(&prompt.input, layout[1]);
+}
+
 fn method_color(method: HttpMethod) -> ratatui::style::Color {
     match method {
         HttpMethod::Get => theme::METHOD_GET,
@@ -732,6 +1653,7 @@ fn method_color(method: HttpMethod) -> ratatui::style::Color {
         HttpMethod::Delete => theme::METHOD_DELETE,
         HttpMethod::Head => theme::METHOD_HEAD,
         HttpMethod::Options => theme::METHOD_OPTIONS,
+        HttpMethod::Ws => theme::METHOD_WS,
     }
 }
 
@@ -7,6 +7,31 @@ pub fn format_json_if_valid(text: &str) -> String {
         .unwrap_or_else(|| text.to_string())
 }
 
+/// Byte count for display (e.g., "1.2 KB", "3.4 MB").
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// The longest prefix shared by all of `words`.
+pub fn common_prefix<'s>(words: &[&'s str]) -> &'s str {
+    let Some(first) = words.first() else { return "" };
+    words.iter().fold(first, |common, word| {
+        let len = common
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, c), _)| i + c.len_utf8());
+        &common[..len]
+    })
+}
+
 pub fn scroll_by(pos: &mut usize, delta: isize, max: usize) {
     if delta < 0 {
         *pos = pos.saturating_sub((-delta) as usize);
@@ -24,3 +49,48 @@ pub fn single_line_textarea(initial: &str) -> TextArea<'static> {
 pub fn textarea_value<'a>(textarea: &'a TextArea<'a>) -> &'a str {
     textarea.lines().first().map(|s| s.as_str()).unwrap_or("")
 }
+
+/// Fold pasted multi-line text onto one line, dropping shell line continuations.
+pub fn join_lines(text: &str) -> String {
+    text.replace("\\\r\n", " ")
+        .replace("\\\n", " ")
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Copy `text` to the system clipboard with an OSC 52 escape sequence, which
+/// most terminals (and tmux with `set-clipboard on`) honor, even over SSH.
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
+
+/// Open `url` in the default browser.
+pub fn open_in_browser(url: &str) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap it without holding up the caller
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}